@@ -3,7 +3,7 @@ use snes_emulator::opcodes;
 use std::{env, fs};
 
 fn detect_boot_phase(system: &System) -> &'static str {
-    let pc = system.cpu.pc;
+    let pc = system.cpu.full_pc();
     let brightness = system.get_ppu().brightness;
     let nmi_enabled = system.get_ppu().nmi_enabled;
     
@@ -55,13 +55,13 @@ fn main() {
     // Configura reset vector
     let reset_low = system.memory.read(0x00FFFC) as u32;
     let reset_high = system.memory.read(0x00FFFD) as u32;
-    system.cpu.pc = (reset_high << 8) | reset_low;
+    system.cpu.set_full_pc((reset_high << 8) | reset_low);
     
     println!("=== INFORMAÇÕES DA ROM ===");
     println!("Título: {}", system.memory.get_rom_title());
     println!("Tipo: {:?}", system.memory.rom_type);
     println!("SRAM: {} bytes", system.memory.sram_size);
-    println!("Reset Vector: ${:04X}", system.cpu.pc);
+    println!("Reset Vector: ${:04X}", system.cpu.full_pc());
     println!("Estado inicial CPU: {}", system.get_cpu_state());
     println!("Estado inicial PPU: Scanline {}, Cycle {}, VBlank: {}", 
              system.get_scanline(), 
@@ -96,7 +96,7 @@ fn main() {
     let mut last_phase = "";
     
     for i in 0..max_instructions {
-        let current_pc = system.cpu.pc;
+        let current_pc = system.cpu.full_pc();
         let opcode = system.memory.read(current_pc);
         
         // ✅ VERIFICA SE O OPCODE É VÁLIDO ANTES DE EXECUTAR
@@ -162,7 +162,7 @@ fn main() {
         // Se foi um BRK, mostra o estado depois
         if opcode == 0x00 {
             println!("📊 Estado CPU depois: {}", system.get_cpu_state());
-            println!("📍 Novo PC: ${:06X}", system.cpu.pc);
+            println!("📍 Novo PC: ${:06X}", system.cpu.full_pc());
             println!("🚨 ============================================\n");
         }
         
@@ -171,7 +171,7 @@ fn main() {
         if current_phase != last_phase {
             println!("\n🔄 ════════ MUDANÇA DE FASE ════════");
             println!("   {} → {}", last_phase, current_phase);
-            println!("   PC: ${:06X} | Instrução: {}", system.cpu.pc, i + 1);
+            println!("   PC: ${:06X} | Instrução: {}", system.cpu.full_pc(), i + 1);
             println!("════════════════════════════════════\n");
             last_phase = current_phase;
         }
@@ -202,7 +202,7 @@ fn main() {
         // Detecta mudança de scanline (para ver se PPU está funcionando)
         let current_scanline = system.get_scanline();
         if current_scanline != last_scanline {
-            if i < 50 || current_scanline % 50 == 0 {
+            if i < 50 || current_scanline.is_multiple_of(50) {
                 println!("  └─ PPU: Scanline {} | VBlank: {}", 
                          current_scanline,
                          system.is_vblank());
@@ -229,14 +229,12 @@ fn main() {
         }
         
         // Detecta entrada em VBlank
-        if system.is_vblank() && !system.get_ppu().frame_complete {
-            if i < 100 || i % 200 == 0 {
-                println!("  └─ VBlank iniciado na instrução {}", i + 1);
-            }
+        if system.is_vblank() && !system.get_ppu().frame_complete && (i < 100 || i % 200 == 0) {
+            println!("  └─ VBlank iniciado na instrução {}", i + 1);
         }
         
         // Detecta loop infinito
-        if current_pc == system.cpu.pc && opcode != 0x00 {  // Ignora BRK
+        if current_pc == system.cpu.full_pc() && opcode != 0x00 {  // Ignora BRK
             println!("\n🔁 Loop infinito detectado em ${:04X}", current_pc);
             println!("   Isso é normal se o programa entrou em loop de espera.");
             break;
@@ -257,7 +255,7 @@ fn main() {
     println!("  - NMI Enabled: {}", system.get_ppu().nmi_enabled);
     
     // Análise de progresso
-    if system.cpu.pc >= 0x8000 && system.cpu.pc <= 0x8048 {
+    if system.cpu.full_pc() >= 0x8000 && system.cpu.full_pc() <= 0x8048 {
         let y_reg = system.cpu.y;
         if y_reg <= 1021 {
             let progress = ((1021 - y_reg as i32) as f32 / 1021.0) * 100.0;
@@ -286,9 +284,9 @@ fn main() {
     println!("  - Frames esperados: ~{}", expected_scanlines / 262);
     
     println!("\n=== ANÁLISE FINAL ===");
-    if system.cpu.pc == brk_vector.into() {
+    if system.cpu.full_pc() == brk_vector.into() {
         println!("✅ BRK tratado corretamente (saltou para BRK handler)");
-    } else if system.cpu.pc > 0x8048 {
+    } else if system.cpu.full_pc() > 0x8048 {
         println!("✅ Programa passou da inicialização da WRAM");
     } else {
         println!("⚠️  Programa ainda na inicialização");
@@ -0,0 +1,370 @@
+// SPC700 mínimo: executa da RAM de 64KB do APU e atende as portas $2140-$2143.
+// Sem DSP: serve para jogos que conversam com o APU além do handshake de boot.
+
+use crate::state::Reader;
+
+const FLAG_N: u8 = 0x80;
+const FLAG_V: u8 = 0x40;
+const FLAG_P: u8 = 0x20;
+const FLAG_H: u8 = 0x08;
+const FLAG_Z: u8 = 0x02;
+const FLAG_C: u8 = 0x01;
+
+// Clock do SPC700 (~1.024MHz) contra o clock da CPU principal (~3.58MHz)
+const SPC_CLOCK: u64 = 1_024_000;
+const CPU_CLOCK: u64 = 3_579_545;
+
+// Estado do protocolo de upload do IPL, emulado em alto nível
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IplState {
+    WaitingKick,
+    Transfer { addr: u16, index: u8 },
+    Done,
+}
+
+pub struct Spc700 {
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub sp: u8,
+    pub pc: u16,
+    pub psw: u8,
+
+    pub ram: Vec<u8>,
+    pub cpu_to_apu: [u8; 4], // Escritos pela CPU, lidos em $F4-$F7
+    pub apu_to_cpu: [u8; 4], // Escritos em $F4-$F7, lidos pela CPU
+
+    pub ipl: IplState,
+    pub halted: bool,
+    pub cycles: u64,
+    pub unknown_opcode: Option<(u8, u16)>, // Último opcode não implementado e o PC dele
+}
+
+impl Default for Spc700 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Spc700 {
+    pub fn new() -> Self {
+        Spc700 {
+            a: 0,
+            x: 0,
+            y: 0,
+            sp: 0xEF,
+            pc: 0xFFC0,
+            psw: 0x02,
+
+            ram: vec![0; 0x10000],
+            cpu_to_apu: [0; 4],
+            apu_to_cpu: [0xAA, 0xBB, 0, 0],
+
+            ipl: IplState::WaitingKick,
+            halted: false,
+            cycles: 0,
+            unknown_opcode: None,
+        }
+    }
+
+    // Estado completo do SPC700 para save states (tamanho fixo)
+    pub(crate) fn save_state(&self, out: &mut Vec<u8>) {
+        let (ipl, ipl_addr, ipl_index) = match self.ipl {
+            IplState::WaitingKick => (0, 0, 0),
+            IplState::Transfer { addr, index } => (1, addr, index),
+            IplState::Done => (2, 0, 0),
+        };
+
+        out.extend_from_slice(&[self.a, self.x, self.y, self.sp, self.psw, self.halted as u8]);
+        out.extend_from_slice(&self.pc.to_le_bytes());
+        out.extend_from_slice(&self.cycles.to_le_bytes());
+        out.extend_from_slice(&self.cpu_to_apu);
+        out.extend_from_slice(&self.apu_to_cpu);
+        out.push(ipl);
+        out.extend_from_slice(&ipl_addr.to_le_bytes());
+        out.push(ipl_index);
+        out.extend_from_slice(&self.ram);
+    }
+
+    // Inverso de save_state; quem chama garante o tamanho da seção antes
+    pub(crate) fn load_state(&mut self, reader: &mut Reader) -> Option<()> {
+        let halted;
+        [self.a, self.x, self.y, self.sp, self.psw, halted] = reader.bytes()?;
+        self.halted = halted != 0;
+        self.pc = reader.u16()?;
+        self.cycles = reader.u64()?;
+        self.cpu_to_apu = reader.bytes()?;
+        self.apu_to_cpu = reader.bytes()?;
+
+        let ipl = reader.u8()?;
+        let addr = reader.u16()?;
+        let index = reader.u8()?;
+        self.ipl = match ipl {
+            0 => IplState::WaitingKick,
+            1 => IplState::Transfer { addr, index },
+            _ => IplState::Done,
+        };
+
+        self.ram.copy_from_slice(&reader.bytes::<0x10000>()?);
+        Some(())
+    }
+
+    // Lado da CPU principal ($2140-$2143)
+    pub fn read_port(&self, port: u16) -> u8 {
+        self.apu_to_cpu[(port & 0x03) as usize]
+    }
+
+    pub fn write_port(&mut self, port: u16, value: u8) {
+        let port = (port & 0x03) as usize;
+        self.cpu_to_apu[port] = value;
+
+        // O IPL só reage quando a CPU escreve na porta 0
+        if port == 0 && self.ipl != IplState::Done {
+            self.ipl_port0_write(value);
+        }
+    }
+
+    fn ipl_port0_write(&mut self, value: u8) {
+        match self.ipl {
+            IplState::WaitingKick => {
+                if value == 0xCC {
+                    self.ipl_begin_block(value);
+                }
+            }
+
+            IplState::Transfer { addr, index } => {
+                if value == index {
+                    self.ram[addr as usize] = self.cpu_to_apu[1];
+                    self.apu_to_cpu[0] = index;
+                    self.ipl = IplState::Transfer {
+                        addr: addr.wrapping_add(1),
+                        index: index.wrapping_add(1),
+                    };
+                } else {
+                    // Índice fora de sequência: novo bloco ou salto
+                    self.ipl_begin_block(value);
+                }
+            }
+
+            IplState::Done => {}
+        }
+    }
+
+    fn ipl_begin_block(&mut self, value: u8) {
+        let addr = self.cpu_to_apu[2] as u16 | (self.cpu_to_apu[3] as u16) << 8;
+        self.apu_to_cpu[0] = value;
+
+        if self.cpu_to_apu[1] == 0 {
+            self.pc = addr;
+            self.ipl = IplState::Done;
+        } else {
+            self.ipl = IplState::Transfer { addr, index: 0 };
+        }
+    }
+
+    // Avança o SPC700 até acompanhar o total de ciclos da CPU principal
+    pub fn catch_up(&mut self, cpu_cycles: u64) {
+        let target = cpu_cycles * SPC_CLOCK / CPU_CLOCK;
+        if target > self.cycles {
+            self.run(target - self.cycles);
+        }
+    }
+
+    pub fn run(&mut self, cycles: u64) {
+        let target = self.cycles + cycles;
+
+        while self.cycles < target {
+            if self.ipl != IplState::Done || self.halted {
+                // Parado no IPL ou em SLEEP/STOP: só o tempo passa
+                self.cycles = target;
+                break;
+            }
+
+            let spent = self.step();
+            self.cycles += spent as u64;
+        }
+    }
+
+    fn read(&self, addr: u16) -> u8 {
+        match addr {
+            0x00F4..=0x00F7 => self.cpu_to_apu[(addr - 0x00F4) as usize],
+            _ => self.ram[addr as usize],
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x00F4..=0x00F7 => self.apu_to_cpu[(addr - 0x00F4) as usize] = value,
+            _ => self.ram[addr as usize] = value,
+        }
+    }
+
+    fn fetch(&mut self) -> u8 {
+        let value = self.read(self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        value
+    }
+
+    fn fetch_word(&mut self) -> u16 {
+        let low = self.fetch() as u16;
+        let high = self.fetch() as u16;
+        (high << 8) | low
+    }
+
+    // Endereço de direct page: página 0 ou 1 conforme a flag P
+    fn dp(&mut self) -> u16 {
+        let offset = self.fetch() as u16;
+        if (self.psw & FLAG_P) != 0 { 0x100 | offset } else { offset }
+    }
+
+    fn push(&mut self, value: u8) {
+        self.write(0x100 | self.sp as u16, value);
+        self.sp = self.sp.wrapping_sub(1);
+    }
+
+    fn pull(&mut self) -> u8 {
+        self.sp = self.sp.wrapping_add(1);
+        self.read(0x100 | self.sp as u16)
+    }
+
+    fn set_flag(&mut self, flag: u8, value: bool) {
+        if value {
+            self.psw |= flag;
+        } else {
+            self.psw &= !flag;
+        }
+    }
+
+    fn set_nz(&mut self, value: u8) -> u8 {
+        self.set_flag(FLAG_Z, value == 0);
+        self.set_flag(FLAG_N, (value & 0x80) != 0);
+        value
+    }
+
+    fn compare(&mut self, register: u8, value: u8) {
+        self.set_flag(FLAG_C, register >= value);
+        self.set_nz(register.wrapping_sub(value));
+    }
+
+    fn adc(&mut self, value: u8) {
+        let carry = (self.psw & FLAG_C) as u16;
+        let result = self.a as u16 + value as u16 + carry;
+
+        self.set_flag(FLAG_C, result > 0xFF);
+        self.set_flag(FLAG_H, (self.a & 0x0F) as u16 + (value & 0x0F) as u16 + carry > 0x0F);
+        self.set_flag(FLAG_V, (!(self.a ^ value) & (self.a ^ result as u8) & 0x80) != 0);
+        self.a = self.set_nz(result as u8);
+    }
+
+    fn branch(&mut self, condition: bool) -> u8 {
+        let offset = self.fetch() as i8;
+        if condition {
+            self.pc = self.pc.wrapping_add_signed(offset as i16);
+            4
+        } else {
+            2
+        }
+    }
+
+    // Executa uma instrução e retorna os ciclos do SPC700
+    pub fn step(&mut self) -> u8 {
+        let opcode = self.fetch();
+
+        match opcode {
+            0x00 => 2, // NOP
+
+            // MOV imediato
+            0xE8 => { let v = self.fetch(); self.a = self.set_nz(v); 2 }
+            0xCD => { let v = self.fetch(); self.x = self.set_nz(v); 2 }
+            0x8D => { let v = self.fetch(); self.y = self.set_nz(v); 2 }
+
+            // MOV registrador <- memória
+            0xE4 => { let addr = self.dp(); let v = self.read(addr); self.a = self.set_nz(v); 3 }
+            0xF8 => { let addr = self.dp(); let v = self.read(addr); self.x = self.set_nz(v); 3 }
+            0xEB => { let addr = self.dp(); let v = self.read(addr); self.y = self.set_nz(v); 3 }
+            0xE5 => { let addr = self.fetch_word(); let v = self.read(addr); self.a = self.set_nz(v); 4 }
+
+            // MOV memória <- registrador
+            0xC4 => { let addr = self.dp(); self.write(addr, self.a); 4 }
+            0xD8 => { let addr = self.dp(); self.write(addr, self.x); 4 }
+            0xCB => { let addr = self.dp(); self.write(addr, self.y); 4 }
+            0xC5 => { let addr = self.fetch_word(); self.write(addr, self.a); 5 }
+            0x8F => { let v = self.fetch(); let addr = self.dp(); self.write(addr, v); 5 }
+
+            // Transferências entre registradores
+            0x7D => { self.a = self.set_nz(self.x); 2 }
+            0x5D => { self.x = self.set_nz(self.a); 2 }
+            0xDD => { self.a = self.set_nz(self.y); 2 }
+            0xFD => { self.y = self.set_nz(self.a); 2 }
+
+            // INC/DEC
+            0xBC => { self.a = self.set_nz(self.a.wrapping_add(1)); 2 }
+            0x9C => { self.a = self.set_nz(self.a.wrapping_sub(1)); 2 }
+            0x3D => { self.x = self.set_nz(self.x.wrapping_add(1)); 2 }
+            0x1D => { self.x = self.set_nz(self.x.wrapping_sub(1)); 2 }
+            0xFC => { self.y = self.set_nz(self.y.wrapping_add(1)); 2 }
+            0xDC => { self.y = self.set_nz(self.y.wrapping_sub(1)); 2 }
+            0xAB => {
+                let addr = self.dp();
+                let v = self.read(addr).wrapping_add(1);
+                let v = self.set_nz(v);
+                self.write(addr, v);
+                4
+            }
+            0x8B => {
+                let addr = self.dp();
+                let v = self.read(addr).wrapping_sub(1);
+                let v = self.set_nz(v);
+                self.write(addr, v);
+                4
+            }
+
+            // Aritmética e comparação
+            0x88 => { let v = self.fetch(); self.adc(v); 2 }
+            0x68 => { let v = self.fetch(); self.compare(self.a, v); 2 }
+            0x64 => { let addr = self.dp(); let v = self.read(addr); self.compare(self.a, v); 3 }
+            0xC8 => { let v = self.fetch(); self.compare(self.x, v); 2 }
+            0xAD => { let v = self.fetch(); self.compare(self.y, v); 2 }
+
+            // Desvios
+            0x2F => self.branch(true),
+            0xF0 => self.branch((self.psw & FLAG_Z) != 0),
+            0xD0 => self.branch((self.psw & FLAG_Z) == 0),
+            0xB0 => self.branch((self.psw & FLAG_C) != 0),
+            0x90 => self.branch((self.psw & FLAG_C) == 0),
+            0x30 => self.branch((self.psw & FLAG_N) != 0),
+            0x10 => self.branch((self.psw & FLAG_N) == 0),
+
+            0x5F => { self.pc = self.fetch_word(); 3 }
+            0x3F => {
+                let target = self.fetch_word();
+                self.push((self.pc >> 8) as u8);
+                self.push(self.pc as u8);
+                self.pc = target;
+                8
+            }
+            0x6F => {
+                let low = self.pull() as u16;
+                let high = self.pull() as u16;
+                self.pc = (high << 8) | low;
+                5
+            }
+
+            // Flags
+            0x60 => { self.psw &= !FLAG_C; 2 }
+            0x80 => { self.psw |= FLAG_C; 2 }
+            0x20 => { self.psw &= !FLAG_P; 2 }
+            0x40 => { self.psw |= FLAG_P; 2 }
+
+            // SLEEP/STOP
+            0xEF | 0xFF => { self.halted = true; 3 }
+
+            // Opcode desconhecido para o núcleo em vez de executar lixo
+            _ => {
+                self.unknown_opcode = Some((opcode, self.pc.wrapping_sub(1)));
+                self.halted = true;
+                2
+            }
+        }
+    }
+}
@@ -1,6 +1,24 @@
 use crate::memory::Memory;
 use crate::opcodes::{get_opcode_info, Operation, AddressingMode, FLAG_CARRY, FLAG_ZERO, FLAG_IRQ, FLAG_DECIMAL, FLAG_OVERFLOW, FLAG_NEGATIVE};
 
+// Endereço de 24 bits a partir de banco e offset
+pub fn addr24(bank: u8, offset: u16) -> u32 {
+    ((bank as u32) << 16) | offset as u32
+}
+
+// Soma um índice a um endereço de 24 bits, com carry para o próximo banco
+// e wrap de $FF:FFFF para $00:0000
+pub fn add_index(addr: u32, index: u16) -> u32 {
+    addr.wrapping_add(index as u32) & 0xFFFFFF
+}
+
+// Erros de execução que a CPU registra em vez de abortar; o host consulta com
+// Cpu::last_error / Cpu::take_error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuError {
+    UnknownOpcode { opcode: u8, pc: u32 }, // pc aponta para o opcode
+}
+
 pub struct Cpu {
 
     // Registers
@@ -8,7 +26,7 @@ pub struct Cpu {
     pub x: u16,     //  Regsiter X
     pub y: u16,     // Register Y
     pub sp: u16,    // Stack Pointer
-    pub pc: u32,    // Program Counter
+    pub pc: u16,    // Program Counter (offset dentro do banco PB)
     pub dp: u16,    // Direct Page Register
     pub db: u8,     // Data Bank Register
     pub pb: u8,     // Program Bank Register
@@ -22,6 +40,15 @@ pub struct Cpu {
     pub e_flag: bool, // Emulation Mode Flag
 
     pub cycles: u64, // Cycle count
+
+    page_crossed: bool, // Leitura indexada da instrução atual cruzou página
+    last_error: Option<CpuError>, // Último erro de execução, até ser consumido
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[allow(dead_code)]
@@ -48,6 +75,8 @@ impl Cpu {
             x_flag: true, // Start in Emulation Mode (8-bit index registers)
             e_flag: true, // Start in Emulation Mode
             cycles: 0,
+            page_crossed: false,
+            last_error: None,
         }
     }
 
@@ -55,7 +84,7 @@ impl Cpu {
         self.a = 0x0000;
         self.x = 0x0000;
         self.y = 0x0000;
-        self.pc = 0x008000;
+        self.pc = 0x8000;
         self.sp = 0x01FF;
         self.dp = 0x0000;
         self.db = 0x00;
@@ -65,44 +94,89 @@ impl Cpu {
         self.x_flag = true;
         self.e_flag = true;
         self.cycles = 0;
+        self.last_error = None;
     }
 
     pub fn step(&mut self, memory: &mut Memory) -> u8 {
-        let opcode = memory.read(self.pc);
-        self.pc += 1;
+        let opcode = memory.read(self.full_pc());
+        self.advance_pc(1);
 
         let cycles = self.execute_instruction(opcode, memory);
         self.cycles += cycles as u64;
         cycles
     }
 
+    // Fetches sequenciais só avançam os 16 bits baixos: o banco do programa não muda
+    pub fn advance_pc(&mut self, count: u16) {
+        self.pc = self.pc.wrapping_add(count);
+    }
+
+    fn pc_offset(&self, offset: u16) -> u32 {
+        addr24(self.pb, self.pc.wrapping_add(offset))
+    }
+
+    // PC de 24 bits: PB no byte alto, o offset de 16 bits em pc
+    pub fn full_pc(&self) -> u32 {
+        addr24(self.pb, self.pc)
+    }
+
+    pub fn set_full_pc(&mut self, addr: u32) {
+        self.pb = (addr >> 16) as u8;
+        self.pc = addr as u16;
+    }
+
+    pub fn last_error(&self) -> Option<CpuError> {
+        self.last_error
+    }
+
+    pub fn take_error(&mut self) -> Option<CpuError> {
+        self.last_error.take()
+    }
+
     pub fn handle_nmi(&mut self, memory: &mut Memory) {
-        let pc_bank = (self.pc >> 16) as u8;
-        let pc_high = ((self.pc >> 8) & 0xFF) as u8;
+        self.enter_interrupt(memory, 0x00FFEA, 0x00FFFA, false);
+    }
+
+    // Entrada comum de interrupções: empilha PB (só nativo), PC e P, liga I, limpa D
+    // e salta pelo vetor do modo atual. BRK/COP marcam o bit 4 do P empilhado em emulação
+    fn enter_interrupt(&mut self, memory: &mut Memory, native_vector: u32, emulation_vector: u32, software: bool) {
+        let pc_bank = self.pb;
+        let pc_high = (self.pc >> 8) as u8;
         let pc_low = self.pc as u8;
 
-        self.push_byte(memory, pc_bank);
+        // Em modo emulação o banco do PC não é empilhado
+        if !self.e_flag {
+            self.push_byte(memory, pc_bank);
+        }
         self.push_byte(memory, pc_high);
         self.push_byte(memory, pc_low);
-        self.push_byte(memory, self.p);
 
-        let nmi_vector = if self.e_flag { 0xFFFA } else { 0x00FFFA };
-        let nmi_low = memory.read(nmi_vector) as u32;
-        let nmi_high = memory.read(nmi_vector + 1) as u32;
-        self.pc = (nmi_high << 8) | nmi_low;
+        let pushed_p = if self.e_flag && software { self.p | 0x10 } else { self.p };
+        self.push_byte(memory, pushed_p);
+
+        let vector = if self.e_flag { emulation_vector } else { native_vector };
+        let low = memory.read(vector) as u32;
+        let high = memory.read(vector + 1) as u32;
+        self.set_full_pc((high << 8) | low);
 
         self.set_flag(Self::FLAG_IRQ);
+        self.clear_flag(Self::FLAG_DECIMAL);
     }
 
     pub fn execute_instruction(&mut self, opcode: u8, memory: &mut Memory) -> u8 {
+        #[cfg(feature = "strict-debug")]
+        self.assert_legal_state(memory);
+
         match get_opcode_info(opcode){
             Some(info) => {
+                self.page_crossed = false;
                 self.execute_operation(info.operation, info.mode, memory);
-                self.adjust_cycles(info.cycles, info.mode)
+                self.adjust_cycles(info.operation, info.cycles, info.mode)
             }
 
+            // Opcode indefinido: vira NOP de 2 ciclos e fica registrado para o host
             None => {
-                println!("Unknown opcode: {:02X} at PC: {:06X}", opcode, self.pc - 1);
+                self.last_error = Some(CpuError::UnknownOpcode { opcode, pc: self.pc_offset(0xFFFF) });
                 2
             }
         }
@@ -112,46 +186,45 @@ impl Cpu {
         match op {
             Operation::LoadA => {
                 let value = self.read_operand(mode, memory, false);
-                self.a = if self.m_flag { (self.a & 0xFF00) | value} else { value };
+                self.a = if self.acc_is_8bit() { (self.a & 0xFF00) | value} else { value };
                 self.update_nz_flags_a();
             }
 
             Operation::LoadX => {
-                let value = self.read_operand(mode, memory, false);
-                self.x = if self.x_flag { (self.x & 0xFF00) | value} else { value };
+                let value = self.read_operand(mode, memory, true);
+                self.x = if self.idx_is_8bit() { (self.x & 0xFF00) | value} else { value };
                 self.update_nz_flags_x();
             }
 
             Operation::LoadY => {
-                let value = self.read_operand(mode, memory, false);
-                self.y = if self.x_flag { (self.y & 0xFF00) | value} else { value };
+                let value = self.read_operand(mode, memory, true);
+                self.y = if self.idx_is_8bit() { (self.y & 0xFF00) | value} else { value };
                 self.update_nz_flags_y();
             }
 
             Operation::StoreA => {
-                let value = if self.m_flag { self.a & 0xFF } else { self.a };
+                let value = if self.acc_is_8bit() { self.a & 0xFF } else { self.a };
                 self.write_operand(mode, memory, value, false);
             }
 
             Operation::StoreX => {
-                let value = if self.x_flag { self.x & 0xFF } else { self.x };
+                let value = if self.idx_is_8bit() { self.x & 0xFF } else { self.x };
                 self.write_operand(mode, memory, value, true);
             }
 
             Operation::StoreY => {
-                let value = if self.x_flag { self.y & 0xFF } else { self.y };
+                let value = if self.idx_is_8bit() { self.y & 0xFF } else { self.y };
                 self.write_operand(mode, memory, value, true);
             }
 
             Operation::StoreZero => {
                 let addr = self.get_effective_address(mode, memory);
 
-                if self.m_flag {
+                if self.acc_is_8bit() {
                     memory.write(addr, 0);
 
                 } else {
-                    memory.write(addr, 0);
-                    memory.write(addr + 1, 0);
+                    self.write_u16_mode(memory, mode, addr, 0);
                 }
             }
 
@@ -168,7 +241,7 @@ impl Cpu {
             Operation::Inc => {
                 match mode {
                     AddressingMode::Implied => {
-                        if self.m_flag {
+                        if self.acc_is_8bit() {
                             let result = (self.a & 0xFF).wrapping_add(1) & 0xFF;
                             self.a = (self.a & 0xFF00) | result;
 
@@ -180,20 +253,23 @@ impl Cpu {
 
                     _=> {
                         let addr = self.get_effective_address(mode, memory);
-                        let is_8bit = self.m_flag;
+                        let is_8bit = self.acc_is_8bit();
 
                         if is_8bit {
-                            let value = memory.read(addr).wrapping_add(1);
+                            let old = memory.read(addr);
+                            let value = old.wrapping_add(1);
+
+                            // Dummy write do valor antigo no modo emulação, como nos shifts
+                            if self.e_flag {
+                                memory.write(addr, old);
+                            }
                             memory.write(addr, value);
-                            self.update_nz_flags(value as u16);
+                            self.update_nz_flags_m(value as u16);
 
                         } else {
-                            let low = memory.read(addr) as u16;
-                            let high = memory.read(addr + 1) as u16;
-                            let value = ((high << 8) | low).wrapping_add(1);
-                            memory.write(addr, value as u8);
-                            memory.write(addr + 1, (value >> 8) as u8);
-                            self.update_nz_flags(value);
+                            let value = self.read_u16_mode(memory, mode, addr).wrapping_add(1);
+                            self.write_u16_mode(memory, mode, addr, value);
+                            self.update_nz_flags_m(value);
                         }
                     }
                 }
@@ -202,7 +278,7 @@ impl Cpu {
             Operation::Dec => {
                 match mode {
                     AddressingMode::Implied => {
-                        if self.m_flag {
+                        if self.acc_is_8bit() {
                             let result = (self.a & 0xFF).wrapping_sub(1) & 0xFF;
                             self.a = (self.a & 0xFF00) | result;
 
@@ -215,20 +291,23 @@ impl Cpu {
 
                     _=> {
                         let addr = self.get_effective_address(mode, memory);
-                        let is_8bit = self.m_flag;
+                        let is_8bit = self.acc_is_8bit();
 
                         if is_8bit {
-                            let value = memory.read(addr).wrapping_sub(1);
+                            let old = memory.read(addr);
+                            let value = old.wrapping_sub(1);
+
+                            // Dummy write do valor antigo no modo emulação, como nos shifts
+                            if self.e_flag {
+                                memory.write(addr, old);
+                            }
                             memory.write(addr, value);
-                            self.update_nz_flags(value as u16);
+                            self.update_nz_flags_m(value as u16);
 
                         } else {
-                            let low = memory.read(addr) as u16;
-                            let high = memory.read(addr + 1) as u16;
-                            let value = ((high << 8) | low).wrapping_sub(1);
-                            memory.write(addr, value as u8);
-                            memory.write(addr + 1, (value >> 8) as u8);
-                            self.update_nz_flags(value);
+                            let value = self.read_u16_mode(memory, mode, addr).wrapping_sub(1);
+                            self.write_u16_mode(memory, mode, addr, value);
+                            self.update_nz_flags_m(value);
                         }
                     }
                 }
@@ -236,7 +315,7 @@ impl Cpu {
 
             Operation::And => {
                 let operand = self.read_operand(mode, memory, false);
-                if self.m_flag {
+                if self.acc_is_8bit() {
                     let result = (self.a & 0xFF) & operand;
                     self.a = (self.a & 0xFF00) | result;
 
@@ -250,7 +329,7 @@ impl Cpu {
 
             Operation::Or => {
                 let operand = self.read_operand(mode, memory, false);
-                if self.m_flag {
+                if self.acc_is_8bit() {
                     let result = (self.a & 0xFF) | operand;
                     self.a = (self.a & 0xFF00) | result;
 
@@ -264,7 +343,7 @@ impl Cpu {
 
             Operation::Xor => {
                 let operand = self.read_operand(mode, memory, false);
-                if self.m_flag {
+                if self.acc_is_8bit() {
                     let result = (self.a & 0xFF) ^ operand;
                     self.a = (self.a & 0xFF00) | result;
 
@@ -276,6 +355,12 @@ impl Cpu {
                 self.update_nz_flags_a();
             }
 
+            // XBA: troca os bytes de A; N/Z sempre do novo byte baixo
+            Operation::ExchangeBA => {
+                self.a = self.a.rotate_left(8);
+                self.update_nz_flags_8(self.a as u8);
+            }
+
             Operation::Xce => {
                 let old_carry = self.get_flag(Self::FLAG_CARRY);
                 let old_emulation = self.e_flag;
@@ -289,36 +374,37 @@ impl Cpu {
                 }
 
                 self.e_flag = old_carry;
+                self.update_mode_flags();
 
-                if !self.e_flag {
-                    self.m_flag = (self.p & 0x20) != 0;
-                    self.x_flag = (self.p & 0x10) != 0;
-
-                } else {
-                    self.m_flag = true;
-                    self.x_flag = true;
+                // Ao voltar para emulação o byte alto do SP é forçado para $01;
+                // indo para nativo o SP é mantido como está
+                if self.e_flag {
+                    self.sp = 0x0100 | (self.sp & 0xFF);
                 }
             }
 
+            // REP/SEP sempre usam imediato de 8 bits, independente de M/X
             Operation::Rep => {
-                let operand = self.read_operand(mode, memory, false) as u8;
+                let operand = memory.read(self.full_pc());
+                self.advance_pc(1);
                 self.p &= !operand;
                 self.update_mode_flags();
             }
 
             Operation::Sep => {
-                let operand = self.read_operand(mode, memory, false) as u8;
+                let operand = memory.read(self.full_pc());
+                self.advance_pc(1);
                 self.p |= operand;
                 self.update_mode_flags();
             }
 
             Operation::Tcd => {
                 self.dp = self.a;
-                self.update_nz_flags_a();
+                self.update_nz_flags_16(self.dp);
             }
 
             Operation::DecX => {
-                if self.x_flag {
+                if self.idx_is_8bit() {
                     let result = (self.x & 0xFF).wrapping_sub(1) & 0xFF;
                     self.x = (self.x & 0xFF00) | result;
 
@@ -331,109 +417,80 @@ impl Cpu {
 
             Operation::Compare => {
                 let operand = self.read_operand(mode, memory, false);
-                let acc_value = if self.m_flag { self.a & 0xFF } else { self.a };
-                self.compare(acc_value, operand);
+                let acc_value = if self.acc_is_8bit() { self.a & 0xFF } else { self.a };
+                self.compare(acc_value, operand, self.acc_is_8bit());
             }
 
             Operation::CompareX => {
                 let operand = self.read_operand(mode, memory, true);
-                let x_value = if self.x_flag { self.x & 0xFF } else { self.x };
-                self.compare(x_value, operand);
+                let x_value = if self.idx_is_8bit() { self.x & 0xFF } else { self.x };
+                self.compare(x_value, operand, self.idx_is_8bit());
             }
 
             Operation::CompareY => {
                 let operand = self.read_operand(mode, memory, true);
-                let y_value = if self.x_flag { self.y & 0xFF } else { self.y };
-                self.compare(y_value, operand);
+                let y_value = if self.idx_is_8bit() { self.y & 0xFF } else { self.y };
+                self.compare(y_value, operand, self.idx_is_8bit());
             }
 
-            Operation::ShiftLeft => {
-                match mode {
-                    AddressingMode::Implied => {
-                        if self.m_flag {
-                            let value = self.a & 0xFF;
-                            self.set_carry_flag((value & 0x80) != 0);
-                            let result = (value << 1) & 0xFF;
-                            self.a = (self.a & 0xFF00) | result;
-                            self.update_nz_flags(result);
+            // TSB/TRB: Z vem de A & memória na largura do acumulador; N/V/C não mudam
+            Operation::TestSetBits | Operation::TestResetBits => {
+                let addr = self.get_effective_address(mode, memory);
+                let set = matches!(op, Operation::TestSetBits);
 
-                        } else {
-                            self.set_carry_flag((self.a & 0x8000) != 0);
-                            self.a <<= 1;
-                            self.update_nz_flags(self.a);
+                if self.acc_is_8bit() {
+                    let value = memory.read(addr) as u16;
+                    let a = self.a & 0xFF;
+                    self.set_zero_flag(value & a == 0);
 
-                        }
+                    let result = if set { value | a } else { value & !a };
+                    if self.e_flag {
+                        memory.write(addr, value as u8);
                     }
+                    memory.write(addr, result as u8);
+                } else {
+                    let value = self.read_u16_mode(memory, mode, addr);
+                    self.set_zero_flag(value & self.a == 0);
 
-                    _=> {
-                        let addr = self.get_effective_address(mode, memory);
-                        let is_8bit = self.m_flag;
-
-                        if is_8bit {
-                            let value = memory.read(addr);
-                            self.set_carry_flag((value & 0x80) != 0);
-                            let result = value << 1;
-                            memory.write(addr, result);
-                            self.update_nz_flags(result as u16);
-
-                        } else {
-                            let low = memory.read(addr) as u16;
-                            let high = memory.read(addr + 1) as u16;
-                            let value = (high << 8) | low;
-                            self.set_carry_flag((value & 0x8000) != 0);
-                            let result = value << 1;
-                            memory.write(addr, result as u8);
-                            memory.write(addr + 1, (result >> 8) as u8);
-                            self.update_nz_flags(result);
-                        }
-                    }
+                    let result = if set { value | self.a } else { value & !self.a };
+                    self.write_u16_mode(memory, mode, addr, result);
                 }
             }
 
-            Operation::ShiftRight => {
+            Operation::ShiftLeft | Operation::ShiftRight | Operation::RotateLeft | Operation::RotateRight => {
                 match mode {
                     AddressingMode::Implied => {
-                        if self.m_flag {
-                            let value = self.a & 0xFF;
-                            self.set_carry_flag((value & 0x01) != 0);
-                            let result = value >> 1;
+                        if self.acc_is_8bit() {
+                            let result = self.shift(op, self.a & 0xFF);
                             self.a = (self.a & 0xFF00) | result;
-                            self.update_nz_flags(result);
-
                         } else {
-                            self.set_carry_flag((self.a & 0x0001) != 0);
-                            self.a >>= 1;
-                            self.update_nz_flags(self.a);
+                            self.a = self.shift(op, self.a);
                         }
                     }
 
                     _=> {
                         let addr = self.get_effective_address(mode, memory);
-                        let is_8bit = self.m_flag;
 
-                        if is_8bit {
+                        if self.acc_is_8bit() {
                             let value = memory.read(addr);
-                            self.set_carry_flag((value & 0x01) != 0);
-                            let result = value >> 1;
-                            memory.write(addr, result);
-                            self.update_nz_flags(result as u16);
+                            let result = self.shift(op, value as u16);
 
-                        } else {
-                            let low = memory.read(addr) as u16;
-                            let high = memory.read(addr + 1) as u16;
-                            let value = (high << 8) | low;
-                            self.set_carry_flag((value & 0x0001) != 0);
-                            let result = value >> 1;
+                            // No modo emulação o RMW escreve o valor antigo antes do resultado
+                            if self.e_flag {
+                                memory.write(addr, value);
+                            }
                             memory.write(addr, result as u8);
-                            memory.write(addr + 1, (result >> 8) as u8);
-                            self.update_nz_flags(result);
+                        } else {
+                            let value = self.read_u16_mode(memory, mode, addr);
+                            let result = self.shift(op, value);
+                            self.write_u16_mode(memory, mode, addr, result);
                         }
                     }
                 }
             }
 
             Operation::TransferAX => {
-                if self.x_flag {
+                if self.idx_is_8bit() {
                     let value = self.a & 0xFF;
                     self.x = (self.x & 0xFF00) | value;
 
@@ -446,7 +503,7 @@ impl Cpu {
             }
 
             Operation::TransferAY => {
-                if self.x_flag {
+                if self.idx_is_8bit() {
                     let value = self.a & 0xFF;
                     self.y = (self.y & 0xFF00) | value;
 
@@ -459,7 +516,7 @@ impl Cpu {
             }
 
             Operation::TransferXA => {
-                if self.m_flag {
+                if self.acc_is_8bit() {
                     let value = self.x & 0xFF;
                     self.a = (self.a & 0xFF00) | value;
 
@@ -472,7 +529,7 @@ impl Cpu {
             }
 
             Operation::TransferYA => {
-                if self.m_flag {
+                if self.acc_is_8bit() {
                     let value = self.y & 0xFF;
                     self.a = (self.a & 0xFF00) | value;
 
@@ -485,7 +542,7 @@ impl Cpu {
             }
 
             Operation::TransferSX => {
-                if self.x_flag {
+                if self.idx_is_8bit() {
                     let value = self.sp & 0xFF;
                     self.x = (self.x & 0xFF00) | value;
 
@@ -502,7 +559,7 @@ impl Cpu {
                     self.sp = 0x0100 | (self.x & 0xFF);
 
                 } else {
-                    if self.x_flag{
+                    if self.idx_is_8bit(){
                         self.sp = (self.sp & 0xFF00) | (self.x & 0xFF);
 
                     } else {
@@ -514,7 +571,7 @@ impl Cpu {
             }
 
             Operation::TransferXY => {
-                if self.x_flag {
+                if self.idx_is_8bit() {
                     let value = self.x & 0xFF;
                     self.y = (self.y & 0xFF00) | value;
 
@@ -528,7 +585,7 @@ impl Cpu {
             }
 
             Operation::TransferYX => {
-                if self.x_flag {
+                if self.idx_is_8bit() {
                     let value = self.y & 0xFF;
                     self.x = (self.x & 0xFF00) | value;
 
@@ -540,34 +597,23 @@ impl Cpu {
                 self.update_nz_flags_x();
             }
 
+            // TSC/TCS sempre transferem os 16 bits de C (B:A), independente de M
             Operation::TransferSC => {
-                if self.m_flag {
-                    let value = self.sp & 0xFF;
-                    self.a = (self.sp & 0xFF00) | value;
-
-                } else {
-                    self.a = self.sp;
-                }
-
-                self.update_nz_flags_a();
+                self.a = self.sp;
+                self.update_nz_flags_16(self.a);
             }
 
             Operation::TransferCS => {
                 if self.e_flag {
-                    self.sp = 0x0100 | (self.sp & 0xFF);
+                    self.sp = 0x0100 | (self.a & 0xFF);
 
                 } else {
-                    if self.m_flag {
-                        self.sp = (self.sp & 0xFF00) | (self.a & 0xFF);
-
-                    } else {
-                        self.sp = self.a;
-                    }
+                    self.sp = self.a;
                 }
             }
 
             Operation::PushA => {
-                if self.m_flag {
+                if self.acc_is_8bit() {
                     self.push_byte(memory, (self.a & 0xFF) as u8);
 
                 } else {
@@ -577,7 +623,7 @@ impl Cpu {
             }
 
             Operation::PullA => {
-                if self.m_flag {
+                if self.acc_is_8bit() {
                     let value = self.pull_byte(memory) as u16;
                     self.a = (self.a & 0xFF00) | value;
 
@@ -590,17 +636,21 @@ impl Cpu {
                 self.update_nz_flags_a();
             }
 
+            // No modo emulação os bits 4-5 (B e não usado) são sempre empilhados como 1
             Operation::PushP => {
-                self.push_byte(memory, self.p);
+                let value = if self.e_flag { self.p | 0x30 } else { self.p };
+                self.push_byte(memory, value);
             }
 
+            // No modo emulação M/X não existem: os bits 4-5 continuam em 1
             Operation::PullP => {
-                self.p = self.pull_byte(memory);
+                let value = self.pull_byte(memory);
+                self.p = if self.e_flag { value | 0x30 } else { value };
                 self.update_mode_flags();
             }
 
             Operation::PushX => {
-                if self.x_flag {
+                if self.idx_is_8bit() {
                     self.push_byte(memory, (self.x & 0xFF) as u8);
 
                 } else {
@@ -610,7 +660,7 @@ impl Cpu {
             }
 
             Operation::PullX => {
-                if self.x_flag {
+                if self.idx_is_8bit() {
                     let value = self.pull_byte(memory) as u16;
                     self.x = (self.x & 0xFF00) | value;
 
@@ -624,7 +674,7 @@ impl Cpu {
             }
 
             Operation::PushY => {
-                if self.x_flag {
+                if self.idx_is_8bit() {
                     self.push_byte(memory, (self.y & 0xFF) as u8);
 
                 } else {
@@ -634,7 +684,7 @@ impl Cpu {
             }
 
             Operation::PullY => {
-                if self.x_flag {
+                if self.idx_is_8bit() {
                     let value = self.pull_byte(memory) as u16;
                     self.y = (self.y & 0xFF00) | value;
 
@@ -650,25 +700,34 @@ impl Cpu {
             Operation::JumpSubroutine => {
                 let target = self.read_address(mode, memory);
 
-                let return_addr = self.pc -1;
+                let return_addr = self.pc.wrapping_sub(1);
                 self.push_byte(memory, (return_addr >> 8) as u8);
                 self.push_byte(memory, return_addr as u8);
 
-                self.pc = target;
+                // JSR abs e JSR (abs,X) ficam no banco do programa
+                self.pc = target as u16;
             }
 
             Operation::ReturnFromSubroutine => {
-                let low = self.pull_byte(memory) as u32;
-                let high = self.pull_byte(memory) as u32;
-                self.pc = ((high << 8) | low) + 1;
+                let low = self.pull_byte(memory) as u16;
+                let high = self.pull_byte(memory) as u16;
+                self.pc = (high << 8) | low;
+                self.advance_pc(1);
             }
 
+            // Como no PLP, em modo emulação os bits 4-5 do P puxado ficam em 1
             Operation::ReturnFromInterrupt => {
-                self.p = self.pull_byte(memory);
-                let low = self.pull_byte(memory) as u32;
-                let high = self.pull_byte(memory) as u32;
+                let value = self.pull_byte(memory);
+                self.p = if self.e_flag { value | 0x30 } else { value };
+                let low = self.pull_byte(memory) as u16;
+                let high = self.pull_byte(memory) as u16;
                 self.pc = (high << 8) | low;
 
+                // Em modo emulação PB não é empilhado e fica como está
+                if !self.e_flag {
+                    self.pb = self.pull_byte(memory);
+                }
+
                 self.update_mode_flags();
             }
 
@@ -676,23 +735,19 @@ impl Cpu {
                 let low = self.pull_stack(memory) as u32;
                 let high = self.pull_stack(memory) as u32;
                 let bank = self.pull_stack(memory) as u32;
-                self.pc = (bank << 16) | (high << 8) | low;
-                self.pc += 1;
+                self.set_full_pc((bank << 16) | (high << 8) | low);
+                self.advance_pc(1);
             }
 
+            // BRK/COP: o byte de assinatura é pulado antes de empilhar o PC
             Operation::SoftwareInterrupt => {
-                self.pc += 1;
-
-                self.push_byte(memory, (self.pc >> 8) as u8);
-                self.push_byte(memory, self.pc as u8);
-                self.push_byte(memory, self.p | 0x10);
-
-                self.p |= Self::FLAG_IRQ;
-
-                let brk_low = memory.read(0x00FFFE) as u32;
-                let brk_high = memory.read(0x00FFFF) as u32;
-                self.pc = (brk_high << 8) | brk_low;
+                self.advance_pc(1);
+                self.enter_interrupt(memory, 0x00FFE6, 0x00FFFE, true);
+            }
 
+            Operation::Coprocessor => {
+                self.advance_pc(1);
+                self.enter_interrupt(memory, 0x00FFE4, 0x00FFF4, true);
             }
 
             Operation::SetFlag(flag) => self.set_flag(flag),
@@ -700,13 +755,13 @@ impl Cpu {
 
             Operation::Jump => {
                 let addr = self.read_address(mode, memory);
-                self.pc = addr;
+                self.pc = addr as u16;
             }
 
             Operation::JumpIndirect => {
                 let ptr = self.read_address(AddressingMode::Absolute, memory);
-                let addr_low = memory.read(ptr) as u32;
-                let addr_high = memory.read(ptr + 1) as u32;
+                let addr_low = memory.read(ptr) as u16;
+                let addr_high = memory.read(ptr + 1) as u16;
                 self.pc = (addr_high << 8) | addr_low;
             }
 
@@ -714,88 +769,117 @@ impl Cpu {
                 let flag_set = self.get_flag(flag);
                 let should_branch = flag_set == condition;
 
-                let offset = memory.read(self.pc) as i8;
-                self.pc += 1;
+                let offset = memory.read(self.full_pc()) as i8;
+                self.advance_pc(1);
 
+                // Desvios relativos só mudam os 16 bits baixos: o banco do programa não muda
                 if should_branch {
-                    self.pc = ((self.pc as i32) + (offset as i32)) as u32;
+                    self.pc = self.pc.wrapping_add_signed(offset as i16);
                 }
             }
 
             Operation::Nop => { /* Do nothing */}
 
-            _ => {
-                println!("Unimplemented operation: {:?} in mode: {:?}", op, mode);
+            // WDM: reservado, só pula o byte de operando
+            Operation::Wdm => {
+                self.advance_pc(1);
+            }
+
+            // MVN/MVP: um byte por execução; o PC volta para o opcode até A dar wrap,
+            // então interrupções são atendidas entre os bytes e a cópia continua depois
+            Operation::BlockMoveNegative | Operation::BlockMovePositive => {
+                let dest_bank = memory.read(self.full_pc());
+                let src_bank = memory.read(self.pc_offset(1));
+                self.advance_pc(2);
+                self.db = dest_bank;
+
+                let value = memory.read(addr24(src_bank, self.x));
+                memory.write(addr24(dest_bank, self.y), value);
+
+                // MVN avança X/Y, MVP recua
+                let delta = if let Operation::BlockMoveNegative = op { 1 } else { 0xFFFF };
+                self.x = self.x.wrapping_add(delta);
+                self.y = self.y.wrapping_add(delta);
+                if self.idx_is_8bit() {
+                    self.x &= 0xFF;
+                    self.y &= 0xFF;
+                }
+
+                // O contador usa sempre os 16 bits de C, independente de M
+                self.a = self.a.wrapping_sub(1);
+                if self.a != 0xFFFF {
+                    self.pc = self.pc.wrapping_sub(3);
+                }
             }
         }
     }
 
     fn read_operand(&mut self, mode: AddressingMode, memory: &mut Memory, use_x_flag: bool) -> u16 {
-        let is_8bit = if use_x_flag { self.x_flag } else { self.m_flag };
+        let is_8bit = if use_x_flag { self.idx_is_8bit() } else { self.acc_is_8bit() };
 
         match mode {
             AddressingMode::Immediate => {
                 if is_8bit {
-                    let value = memory.read(self.pc) as u16;
-                    self.pc += 1;
+                    let value = memory.read(self.full_pc()) as u16;
+                    self.advance_pc(1);
                     value
                 } else {
-                    let low = memory.read(self.pc) as u16;
-                    let high = memory.read(self.pc + 1) as u16;
-                    self.pc += 2;
+                    let low = memory.read(self.full_pc()) as u16;
+                    let high = memory.read(self.pc_offset(1)) as u16;
+                    self.advance_pc(2);
                     (high << 8) | low
                 }
             }
 
             AddressingMode::DirectPage => {
-                let addr = self.dp + memory.read(self.pc) as u16;
-                self.pc += 1;
+                let addr = self.dp.wrapping_add(memory.read(self.full_pc()) as u16);
+                self.advance_pc(1);
 
                 if is_8bit {
-                    memory.read(addr as u32) as u16
+                    memory.read(addr24(0, addr)) as u16
                 } else {
-                    let low = memory.read(addr as u32) as u16;
-                    let high = memory.read((addr + 1) as u32) as u16;
+                    let low = memory.read(addr24(0, addr)) as u16;
+                    let high = memory.read(addr24(0, addr.wrapping_add(1))) as u16;
                     (high << 8) | low
                 }
             }
 
             AddressingMode:: DirectPageIndexedX => {
-                let base = memory.read(self.pc) as u16;
-                self.pc += 1;
-                let addr = (self.dp + base + (self.x & 0xFF)) & 0xFFFF;
+                let base = memory.read(self.full_pc()) as u16;
+                self.advance_pc(1);
+                let addr = self.direct_page_indexed(base, self.x & 0xFF);
 
                 if is_8bit {
-                    memory.read(addr as u32) as u16
+                    memory.read(addr24(0, addr)) as u16
                 } else {
-                    let low = memory.read(addr as u32) as u16;
-                    let high = memory.read((addr + 1) as u32) as u16;
+                    let low = memory.read(addr24(0, addr)) as u16;
+                    let high = memory.read(addr24(0, addr.wrapping_add(1))) as u16;
                     (high << 8) | low
                 }
             }
 
             AddressingMode::DirectPageIndexedY => {
-                let base = memory.read(self.pc) as u16;
-                self.pc += 1;
-                let addr = (self.dp + base + (self.y & 0xFF)) & 0xFFFF;
+                let base = memory.read(self.full_pc()) as u16;
+                self.advance_pc(1);
+                let addr = self.direct_page_indexed(base, self.y & 0xFF);
 
                 if is_8bit {
-                    memory.read(addr as u32) as u16
+                    memory.read(addr24(0, addr)) as u16
                 } else {
-                    let low = memory.read(addr as u32) as u16;
-                    let high = memory.read((addr + 1) as u32) as u16;
+                    let low = memory.read(addr24(0, addr)) as u16;
+                    let high = memory.read(addr24(0, addr.wrapping_add(1))) as u16;
                     (high << 8) | low
                 }
             }
 
             AddressingMode::Absolute => {
-                let addr = self.read_address(mode, memory);
+                let addr = addr24(self.db, self.read_address(mode, memory) as u16);
 
                 if is_8bit {
                     memory.read(addr) as u16
                 } else {
                     let low = memory.read(addr) as u16;
-                    let high = memory.read(addr + 1) as u16;
+                    let high = memory.read(add_index(addr, 1)) as u16;
                     (high << 8) | low
                 }
             }
@@ -808,88 +892,127 @@ impl Cpu {
 
                 } else {
                     let low = memory.read(addr) as u16;
-                    let high = memory.read(addr + 1) as u16;
+                    let high = memory.read(add_index(addr, 1)) as u16;
                     (high << 8) | low
                 }
             }
 
             AddressingMode::AbsoluteIndexedX => {
-                let base = self.read_address(AddressingMode::Absolute, memory);
-                let addr = base + (self.x & 0xFFFF) as u32;
+                let base = addr24(self.db, self.read_address(AddressingMode::Absolute, memory) as u16);
+                let addr = add_index(base, self.x);
+                self.page_crossed = ((base ^ addr) & 0xFFFF00) != 0;
 
                 if is_8bit {
                     memory.read(addr) as u16
                 } else {
                     let low = memory.read(addr) as u16;
-                    let high = memory.read(addr + 1) as u16;
+                    let high = memory.read(add_index(addr, 1)) as u16;
                     (high << 8) | low
                 }
             }
 
             AddressingMode::AbsoluteIndexedY => {
-                let base = self.read_address(AddressingMode::Absolute, memory);
-                let addr = base + (self.y & 0xFFFF) as u32;
+                let base = addr24(self.db, self.read_address(AddressingMode::Absolute, memory) as u16);
+                let addr = add_index(base, self.y);
+                self.page_crossed = ((base ^ addr) & 0xFFFF00) != 0;
 
                 if is_8bit {
                     memory.read(addr) as u16
                 } else {
                     let low = memory.read(addr) as u16;
-                    let high = memory.read(addr + 1) as u16;
+                    let high = memory.read(add_index(addr, 1)) as u16;
                     (high << 8) | low
                 }
             }
 
             AddressingMode::AbsoluteLongIndexedX => {
                 let base = self.read_address(AddressingMode::AbsoluteLong, memory);
-                let addr = base + (self.x & 0xFFFF) as u32;
+                let addr = add_index(base, self.x);
 
                 if is_8bit {
                     memory.read(addr) as u16
                 } else {
                     let low = memory.read(addr) as u16;
-                    let high = memory.read(addr + 1) as u16;
+                    let high = memory.read(add_index(addr, 1)) as u16;
                     (high << 8) | low
                 }
             }
 
             AddressingMode::IndirectIndexed => {
-                let dp_addr = (self.dp + memory.read(self.pc) as u16) & 0xFFFF;
-                self.pc += 1;
+                let dp_addr = self.dp.wrapping_add(memory.read(self.full_pc()) as u16);
+                self.advance_pc(1);
 
-                let prt_low = memory.read(dp_addr as u32) as u32;
-                let ptr_high = memory.read(((dp_addr + 1) & 0xFFFF) as u32) as u32;
-                let base_addr = (ptr_high << 8) | prt_low;
-                let addr = base_addr + (self.y & 0xFFFF) as u32;
+                let ptr_low = memory.read(addr24(0, dp_addr)) as u16;
+                let ptr_high = memory.read(addr24(0, dp_addr.wrapping_add(1))) as u16;
+                let base_addr = addr24(self.db, (ptr_high << 8) | ptr_low);
+                let addr = add_index(base_addr, self.y);
+                self.page_crossed = ((base_addr ^ addr) & 0xFFFF00) != 0;
 
                 if is_8bit{
                     memory.read(addr) as u16
                 } else {
                     let low = memory.read(addr) as u16;
-                    let high = memory.read(addr + 1) as u16;
+                    let high = memory.read(add_index(addr, 1)) as u16;
                     (high << 8) | low
                 }
 
             }
 
             AddressingMode::IndexedIndirect => {
-                let base = memory.read(self.pc) as u16;
-                self.pc += 1;
-                let dp_addr = (self.dp + base + (self.x & 0xFF)) & 0xFFFF;
+                let base = memory.read(self.full_pc()) as u16;
+                self.advance_pc(1);
+                let dp_addr = self.direct_page_indexed(base, self.x & 0xFF);
 
-                let ptr_low = memory.read(dp_addr as u32) as u32;
-                let ptr_high = memory.read(((dp_addr + 1) & 0xFFFF) as u32) as u32;
-                let addr = (ptr_high << 8) | ptr_low;
+                // Ponteiro lido no banco 0 (com wrap em $FFFF), endereço final em DB:ponteiro
+                let ptr_low = memory.read(addr24(0, dp_addr)) as u16;
+                let ptr_high = memory.read(addr24(0, dp_addr.wrapping_add(1))) as u16;
+                let addr = addr24(self.db, (ptr_high << 8) | ptr_low);
 
                 if is_8bit{
                     memory.read(addr) as u16
 
                 } else {
                     let low = memory.read(addr) as u16;
-                    let high = memory.read(addr + 1) as u16;
+                    let high = memory.read(add_index(addr, 1)) as u16;
+                    (high << 8) | low
+                }
+            }
+
+            AddressingMode::DirectPageIndirect |
+            AddressingMode::DirectPageIndirectLong |
+            AddressingMode::DirectPageIndirectLongIndexedY => {
+                let addr = self.indirect_address(mode, memory);
+
+                if is_8bit {
+                    memory.read(addr) as u16
+                } else {
+                    self.read_u16(memory, addr)
+                }
+            }
+
+            // sr,S fica no banco 0 com wrap em $FFFF; (sr,S),Y carrega para o banco seguinte
+            AddressingMode::StackRelative => {
+                let addr = self.stack_relative_address(mode, memory);
+
+                if is_8bit {
+                    memory.read(addr) as u16
+                } else {
+                    let low = memory.read(addr) as u16;
+                    let high = memory.read(addr24(0, (addr as u16).wrapping_add(1))) as u16;
                     (high << 8) | low
                 }
             }
 
+            AddressingMode::StackRelativeIndirectIndexed => {
+                let addr = self.stack_relative_address(mode, memory);
+
+                if is_8bit {
+                    memory.read(addr) as u16
+                } else {
+                    self.read_u16(memory, addr)
+                }
+            }
+
             _ => {
                 println!("Unsupported addressing mode for read_operand: {:?}", mode);
                 0
@@ -898,144 +1021,224 @@ impl Cpu {
     }
 
     fn write_operand(&mut self, mode: AddressingMode, memory: &mut Memory, value: u16, use_x_flag: bool) {
-        let is_8bit = if use_x_flag { self.x_flag } else { self.m_flag };
+        let is_8bit = if use_x_flag { self.idx_is_8bit() } else { self.acc_is_8bit() };
 
-        match mode {
+        // Modos de direct page dão wrap no banco 0; os demais carregam para o banco seguinte
+        let (addr, direct) = match mode {
             AddressingMode::DirectPage => {
-                let addr = self.dp + memory.read(self.pc) as u16;
-                self.pc += 1;
-
-                memory.write(addr as u32, value as u8);
-
-                if !is_8bit {
-                    memory.write((addr + 1) as u32, (value >> 8) as u8);
-                }
+                let addr = self.dp.wrapping_add(memory.read(self.full_pc()) as u16);
+                self.advance_pc(1);
+                (addr24(0, addr), true)
             }
 
             AddressingMode::DirectPageIndexedX => {
-                let base = memory.read(self.pc) as u16;
-                self.pc += 1;
-                let addr = (self.dp + base + (self.x & 0xFF)) & 0xFFFF;
-
-                memory.write(addr as u32, value as u8);
-                if !is_8bit {
-                    memory.write((addr + 1) as u32, (value >> 8) as u8);
-                }
+                let base = memory.read(self.full_pc()) as u16;
+                self.advance_pc(1);
+                (addr24(0, self.direct_page_indexed(base, self.x & 0xFF)), true)
             }
 
             AddressingMode::DirectPageIndexedY => {
-                let base = memory.read(self.pc) as u16;
-                self.pc += 1;
-                let addr = (self.dp + base + (self.y & 0xFF)) & 0xFFFF;
-
-                memory.write(addr as u32, value as u8);
-                if !is_8bit {
-                    memory.write((addr + 1) as u32, (value >> 8) as u8);
-                }
+                let base = memory.read(self.full_pc()) as u16;
+                self.advance_pc(1);
+                (addr24(0, self.direct_page_indexed(base, self.y & 0xFF)), true)
             }
 
             AddressingMode::Absolute => {
-                let addr = self.read_address(mode, memory);
-
-                memory.write(addr, value as u8);
-                if !is_8bit {
-                    memory.write(addr + 1, (value >> 8) as u8);
-                }
+                (addr24(self.db, self.read_address(mode, memory) as u16), false)
             }
 
-            AddressingMode::AbsoluteLong => {
-                let addr = self.read_address(mode, memory);
-
-                memory.write(addr, value as u8);
-                if !is_8bit {
-                    memory.write(addr + 1, (value >> 8) as u8);
-                }
-            }
+            AddressingMode::AbsoluteLong => (self.read_address(mode, memory), false),
 
             AddressingMode::AbsoluteIndexedX => {
-                let base = self.read_address(AddressingMode::Absolute, memory);
-                let addr = base + (self.x & 0xFFFF) as u32;
-
-                memory.write(addr, value as u8);
-                if !is_8bit {
-                    memory.write(addr + 1, (value >> 8) as u8);
-                }
+                let base = addr24(self.db, self.read_address(AddressingMode::Absolute, memory) as u16);
+                (add_index(base, self.x), false)
             }
 
             AddressingMode::AbsoluteIndexedY => {
-                let base = self.read_address(AddressingMode::Absolute, memory);
-                let addr = base + (self.y & 0xFFFF) as u32;
-
-                memory.write(addr, value as u8);
-                if !is_8bit {
-                    memory.write(addr + 1, (value >> 8) as u8);
-                }
+                let base = addr24(self.db, self.read_address(AddressingMode::Absolute, memory) as u16);
+                (add_index(base, self.y), false)
             }
 
             AddressingMode::AbsoluteLongIndexedX => {
                 let base = self.read_address(AddressingMode::AbsoluteLong, memory);
-                let addr = base + (self.x & 0xFFFF) as u32;
-
-                memory.write(addr, value as u8);
-                if !is_8bit {
-                    memory.write(addr + 1, (value >> 8) as u8);
-                }
+                (add_index(base, self.x), false)
             }
 
             AddressingMode::IndirectIndexed => {
-                let dp_addr = (self.dp + memory.read(self.pc) as u16) & 0xFFFF;
-                self.pc += 1;
+                let dp_addr = self.dp.wrapping_add(memory.read(self.full_pc()) as u16);
+                self.advance_pc(1);
 
-                let ptr_low = memory.read(dp_addr as u32) as u32;
-                let ptr_high = memory.read(((dp_addr + 1) & 0xFFFF) as u32) as u32;
-                let base_addr = (ptr_high << 8) | ptr_low;
-                let addr = base_addr + (self.y & 0xFFFF) as u32;
-
-                memory.write(addr, value as u8);
-                if !is_8bit {
-                    memory.write(addr + 1, (value >> 8) as u8);
-                }
+                let ptr_low = memory.read(addr24(0, dp_addr)) as u16;
+                let ptr_high = memory.read(addr24(0, dp_addr.wrapping_add(1))) as u16;
+                let base_addr = addr24(self.db, (ptr_high << 8) | ptr_low);
+                (add_index(base_addr, self.y), false)
             }
 
             AddressingMode::IndexedIndirect => {
-                let base = memory.read(self.pc) as u16;
-                self.pc += 1;
-                let dp_addr = (self.dp + base + (self.x & 0xFF)) & 0xFFFF;
+                let base = memory.read(self.full_pc()) as u16;
+                self.advance_pc(1);
+                let dp_addr = self.direct_page_indexed(base, self.x & 0xFF);
 
-                let ptr_low = memory.read(dp_addr as u32) as u32;
-                let ptr_high = memory.read(((dp_addr + 1) & 0xFFFF) as u32) as u32;
-                let addr = (ptr_high << 8) | ptr_low;
+                // Ponteiro lido no banco 0 (com wrap em $FFFF), endereço final em DB:ponteiro
+                let ptr_low = memory.read(addr24(0, dp_addr)) as u16;
+                let ptr_high = memory.read(addr24(0, dp_addr.wrapping_add(1))) as u16;
+                (addr24(self.db, (ptr_high << 8) | ptr_low), false)
+            }
 
-                memory.write(addr, value as u8);
-                if !is_8bit {
-                    memory.write(addr + 1, (value >> 8) as u8);
-                }
+            AddressingMode::DirectPageIndirect |
+            AddressingMode::DirectPageIndirectLong |
+            AddressingMode::DirectPageIndirectLongIndexedY => {
+                (self.indirect_address(mode, memory), false)
+            }
+
+            AddressingMode::StackRelative => (self.stack_relative_address(mode, memory), true),
+
+            AddressingMode::StackRelativeIndirectIndexed => {
+                (self.stack_relative_address(mode, memory), false)
             }
 
             _ => {
                 println!("Unsupported addressing mode for write_operand: {:?}", mode);
+                return;
             }
+        };
+
+        if is_8bit {
+            memory.write(addr, value as u8);
+        } else if direct {
+            self.write_u16_bank0(memory, addr as u16, value);
+        } else {
+            self.write_u16(memory, addr, value);
         }
     }
 
+    // Endereço efetivo da instrução em opcode_addr sem executá-la (usa peek e o estado atual)
+    pub fn resolve_address(&self, memory: &Memory, opcode_addr: u32) -> Option<u32> {
+        let info = get_opcode_info(memory.peek(opcode_addr))?;
+        let bank = opcode_addr & 0xFF0000;
+        let operand = |i: u16| memory.peek(bank | (opcode_addr as u16).wrapping_add(i) as u32);
+        let peek_ptr = |addr: u16| {
+            memory.peek(addr24(0, addr)) as u16 | (memory.peek(addr24(0, addr.wrapping_add(1))) as u16) << 8
+        };
+
+        let byte = operand(1) as u16;
+        let word = byte | (operand(2) as u16) << 8;
+        let long = addr24(operand(3), word);
+        let jump = matches!(info.operation, Operation::Jump | Operation::JumpSubroutine);
+
+        let addr = match info.mode {
+            AddressingMode::Implied | AddressingMode::Immediate => return None,
+
+            AddressingMode::DirectPage => addr24(0, self.dp.wrapping_add(byte)),
+            AddressingMode::DirectPageIndexedX => addr24(0, self.direct_page_indexed(byte, self.x & 0xFF)),
+            AddressingMode::DirectPageIndexedY => addr24(0, self.direct_page_indexed(byte, self.y & 0xFF)),
+
+            AddressingMode::Absolute if jump => bank | word as u32,
+            AddressingMode::Absolute => addr24(self.db, word),
+            AddressingMode::AbsoluteIndexedX => add_index(addr24(self.db, word), self.x),
+            AddressingMode::AbsoluteIndexedY => add_index(addr24(self.db, word), self.y),
+            AddressingMode::AbsoluteLong => long,
+            AddressingMode::AbsoluteLongIndexedX => add_index(long, self.x),
+
+            // Instruções de salto por ponteiro: devolve o endereço do ponteiro lido
+            AddressingMode::Indirect => addr24(0, word),
+            AddressingMode::AbsoluteIndexedIndirect => bank | word.wrapping_add(self.x) as u32,
+
+            AddressingMode::IndirectIndexed => {
+                add_index(addr24(self.db, peek_ptr(self.dp.wrapping_add(byte))), self.y)
+            }
+            AddressingMode::IndexedIndirect => {
+                addr24(self.db, peek_ptr(self.direct_page_indexed(byte, self.x & 0xFF)))
+            }
+            AddressingMode::DirectPageIndirect => addr24(self.db, peek_ptr(self.dp.wrapping_add(byte))),
+            AddressingMode::DirectPageIndirectLong | AddressingMode::DirectPageIndirectLongIndexedY => {
+                let dp_addr = self.dp.wrapping_add(byte);
+                let addr = addr24(memory.peek(addr24(0, dp_addr.wrapping_add(2))), peek_ptr(dp_addr));
+
+                if let AddressingMode::DirectPageIndirectLongIndexedY = info.mode {
+                    add_index(addr, self.y)
+                } else {
+                    addr
+                }
+            }
+
+            AddressingMode::StackRelative => addr24(0, self.sp.wrapping_add(byte)),
+            AddressingMode::StackRelativeIndirectIndexed => {
+                add_index(addr24(self.db, peek_ptr(self.sp.wrapping_add(byte))), self.y)
+            }
+        };
+
+        Some(addr)
+    }
+
+    // Destino de (dp), [dp] e [dp],Y: ponteiro lido no banco 0 com wrap em $FFFF
+    fn indirect_address(&mut self, mode: AddressingMode, memory: &mut Memory) -> u32 {
+        let dp_addr = self.dp.wrapping_add(memory.read(self.full_pc()) as u16);
+        self.advance_pc(1);
+
+        let ptr_low = memory.read(addr24(0, dp_addr)) as u16;
+        let ptr_high = memory.read(addr24(0, dp_addr.wrapping_add(1))) as u16;
+        let ptr = (ptr_high << 8) | ptr_low;
+
+        match mode {
+            AddressingMode::DirectPageIndirect => addr24(self.db, ptr),
+
+            _ => {
+                let bank = memory.read(addr24(0, dp_addr.wrapping_add(2)));
+                let addr = addr24(bank, ptr);
+
+                if let AddressingMode::DirectPageIndirectLongIndexedY = mode {
+                    add_index(addr, self.y)
+                } else {
+                    addr
+                }
+            }
+        }
+    }
+
+    // Destino de sr,S e (sr,S),Y: S + offset no banco 0; o ponteiro indireto usa DB e soma Y
+    fn stack_relative_address(&mut self, mode: AddressingMode, memory: &mut Memory) -> u32 {
+        let addr = self.sp.wrapping_add(memory.read(self.full_pc()) as u16);
+        self.advance_pc(1);
+
+        if let AddressingMode::StackRelative = mode {
+            return addr24(0, addr);
+        }
+
+        let ptr_low = memory.read(addr24(0, addr)) as u16;
+        let ptr_high = memory.read(addr24(0, addr.wrapping_add(1))) as u16;
+        add_index(addr24(self.db, (ptr_high << 8) | ptr_low), self.y)
+    }
+
     fn read_address(&mut self, mode: AddressingMode, memory: &mut Memory) -> u32 {
         match mode {
             AddressingMode::Absolute => {
-                let addr_low = memory.read(self.pc) as u32;
-                let addr_high = memory.read(self.pc + 1) as u32;
-                self.pc += 2;
+                let addr_low = memory.read(self.full_pc()) as u32;
+                let addr_high = memory.read(self.pc_offset(1)) as u32;
+                self.advance_pc(2);
                 (addr_high << 8) | addr_low
                 
             }
 
             AddressingMode::AbsoluteLong => {
-                let addr_low = memory.read(self.pc) as u32;
-                let addr_mid = memory.read(self.pc + 1) as u32;
-                let addr_high = memory.read(self.pc + 2) as u32;
-                self.pc += 3;
+                let addr_low = memory.read(self.full_pc()) as u32;
+                let addr_mid = memory.read(self.pc_offset(1)) as u32;
+                let addr_high = memory.read(self.pc_offset(2)) as u32;
+                self.advance_pc(3);
                 (addr_high << 16) | (addr_mid << 8) | addr_low
             }
 
+            // (abs,X): ponteiro e destino ficam no banco do programa
+            AddressingMode::AbsoluteIndexedIndirect => {
+                let base = self.read_address(AddressingMode::Absolute, memory) as u16;
+                let ptr = base.wrapping_add(self.x);
+                let bank = (self.pb as u32) << 16;
+
+                let low = memory.read(bank | ptr as u32) as u32;
+                let high = memory.read(bank | ptr.wrapping_add(1) as u32) as u32;
+                bank | (high << 8) | low
+            }
+
             _ => {
                 println!("Unsupported addressing mode for read_address: {:?}", mode);
                 0
@@ -1043,25 +1246,63 @@ impl Cpu {
         }
     }
 
-    fn adjust_cycles(&self, base_cycles: u8, mode: AddressingMode) -> u8 {
+    fn adjust_cycles(&self, op: Operation, base_cycles: u8, mode: AddressingMode) -> u8 {
+        let mut cycles = base_cycles;
+
         match mode {
-            AddressingMode:: Immediate => {
-                if !self.m_flag || !self.x_flag {
-                    base_cycles + 1
-                } else {
-                    base_cycles
-                }
-            }
+            AddressingMode::Immediate if !self.acc_is_8bit() || !self.idx_is_8bit() => cycles += 1,
+
+            // Modos de direct page custam +1 quando o byte baixo de DP não é zero
+            AddressingMode::DirectPage |
+            AddressingMode::DirectPageIndexedX |
+            AddressingMode::DirectPageIndexedY |
+            AddressingMode::IndirectIndexed |
+            AddressingMode::IndexedIndirect |
+            AddressingMode::DirectPageIndirect |
+            AddressingMode::DirectPageIndirectLong |
+            AddressingMode::DirectPageIndirectLongIndexedY if (self.dp & 0xFF) != 0 => cycles += 1,
+
+            // Stack relative só é usado por operações do acumulador: +1 para o byte alto com M=0
+            AddressingMode::StackRelative |
+            AddressingMode::StackRelativeIndirectIndexed if !self.acc_is_8bit() => cycles += 1,
+
+            _ => {}
+        }
 
-            _ => base_cycles
+        // Leituras indexadas só pagam o ciclo extra ao cruzar página;
+        // escritas indexadas sempre pagam e já trazem o ciclo na tabela
+        if self.page_crossed {
+            cycles += 1;
         }
+
+        match op {
+            // Read-modify-write em memória: leitura e escrita extras com M=0
+            Operation::Inc | Operation::Dec |
+            Operation::ShiftLeft | Operation::ShiftRight |
+            Operation::RotateLeft | Operation::RotateRight |
+            Operation::TestSetBits | Operation::TestResetBits
+                if !matches!(mode, AddressingMode::Implied) && !self.acc_is_8bit() => cycles += 2,
+
+            // No modo nativo BRK/COP também empilham o PB
+            Operation::SoftwareInterrupt | Operation::Coprocessor if !self.e_flag => cycles += 1,
+
+            // Push/pull de 16 bits movem um byte a mais
+            Operation::PushA | Operation::PullA if !self.acc_is_8bit() => cycles += 1,
+
+            Operation::PushX | Operation::PullX |
+            Operation::PushY | Operation::PullY if !self.idx_is_8bit() => cycles += 1,
+
+            _ => {}
+        }
+
+        cycles
     }
 
     fn adc(&mut self, operand: u16) {
-        let acc_value = if self.m_flag { self.a & 0xFF } else { self.a };
+        let acc_value = if self.acc_is_8bit() { self.a & 0xFF } else { self.a };
         let carry = if self.get_flag(Self::FLAG_CARRY) { 1 } else { 0 };
 
-        if self.m_flag{
+        if self.acc_is_8bit(){
             let result = acc_value + operand + carry;
 
             self.set_carry_flag(result > 0xFF);
@@ -1082,10 +1323,10 @@ impl Cpu {
     }
 
     fn sbc(&mut self, operand: u16) {
-        let acc_value = if self.m_flag {self.a & 0xFF } else { self.a };
+        let acc_value = if self.acc_is_8bit() {self.a & 0xFF } else { self.a };
         let carry = if self.get_flag(Self::FLAG_CARRY) { 0 } else { 1 };
 
-        if self.m_flag {
+        if self.acc_is_8bit() {
             let result = acc_value as i16 - operand as i16 - carry;
 
             self.set_carry_flag(result >= 0);
@@ -1105,8 +1346,86 @@ impl Cpu {
         self.update_nz_flags_a();
     }
 
-    fn compare(&mut self, register_value: u16, operand: u16) {
-        let result = register_value as i16 - operand as i16;
+    // ASL/LSR/ROL/ROR na largura de M, atualizando C, N e Z
+    fn shift(&mut self, op: Operation, value: u16) -> u16 {
+        let (msb, mask) = if self.acc_is_8bit() { (0x80, 0xFF) } else { (0x8000, 0xFFFF) };
+        let carry_in = self.get_flag(Self::FLAG_CARRY);
+
+        let result = match op {
+            Operation::ShiftLeft => {
+                self.set_carry_flag((value & msb) != 0);
+                (value << 1) & mask
+            }
+
+            Operation::ShiftRight => {
+                self.set_carry_flag((value & 0x01) != 0);
+                value >> 1
+            }
+
+            Operation::RotateLeft => {
+                self.set_carry_flag((value & msb) != 0);
+                ((value << 1) | carry_in as u16) & mask
+            }
+
+            _ => {
+                self.set_carry_flag((value & 0x01) != 0);
+                (value >> 1) | if carry_in { msb } else { 0 }
+            }
+        };
+
+        self.update_nz_flags_m(result);
+        result
+    }
+
+    // Acesso de 16 bits a dados: o byte alto segue para o próximo banco
+    fn read_u16(&self, memory: &Memory, addr: u32) -> u16 {
+        let low = memory.read(addr) as u16;
+        let high = memory.read(add_index(addr, 1)) as u16;
+        (high << 8) | low
+    }
+
+    fn write_u16(&self, memory: &mut Memory, addr: u32, value: u16) {
+        memory.write(addr, value as u8);
+        memory.write(add_index(addr, 1), (value >> 8) as u8);
+    }
+
+    // Direct page: o byte alto dá wrap em $FFFF dentro do banco 0
+    fn write_u16_bank0(&self, memory: &mut Memory, addr: u16, value: u16) {
+        memory.write(addr24(0, addr), value as u8);
+        memory.write(addr24(0, addr.wrapping_add(1)), (value >> 8) as u8);
+    }
+
+    fn read_u16_bank0(&self, memory: &Memory, addr: u16) -> u16 {
+        let low = memory.read(addr24(0, addr)) as u16;
+        let high = memory.read(addr24(0, addr.wrapping_add(1))) as u16;
+        (high << 8) | low
+    }
+
+    // Acesso de 16 bits no endereço efetivo de uma instrução de memória (STZ, INC/DEC,
+    // shifts): direct page dá wrap no banco 0, os outros modos seguem para o próximo banco
+    fn read_u16_mode(&self, memory: &Memory, mode: AddressingMode, addr: u32) -> u16 {
+        if Self::is_direct_page(mode) {
+            self.read_u16_bank0(memory, addr as u16)
+        } else {
+            self.read_u16(memory, addr)
+        }
+    }
+
+    fn write_u16_mode(&self, memory: &mut Memory, mode: AddressingMode, addr: u32, value: u16) {
+        if Self::is_direct_page(mode) {
+            self.write_u16_bank0(memory, addr as u16, value);
+        } else {
+            self.write_u16(memory, addr, value);
+        }
+    }
+
+    fn is_direct_page(mode: AddressingMode) -> bool {
+        matches!(mode, AddressingMode::DirectPage | AddressingMode::DirectPageIndexedX)
+    }
+
+    // A largura vem do chamador: M para CMP, X para CPX/CPY
+    fn compare(&mut self, register_value: u16, operand: u16, is_8bit: bool) {
+        let result = register_value.wrapping_sub(operand);
 
         self.set_carry_flag(register_value >= operand);
 
@@ -1116,47 +1435,56 @@ impl Cpu {
             self.p &= !Self::FLAG_ZERO;
         }
 
-        let test_bit = if self.m_flag || self.x_flag { 0x80 } else { 0x8000 };
+        let test_bit = if is_8bit { 0x80 } else { 0x8000 };
 
-        if ((result as u16) & test_bit) != 0 {
+        if (result & test_bit) != 0 {
             self.p |= Self::FLAG_NEGATIVE;
         } else {
             self.p &= !Self::FLAG_NEGATIVE;
         }
     }
 
+    // dp + base + índice; no modo emulação com DL = 0 o resultado fica na mesma página
+    fn direct_page_indexed(&self, base: u16, index: u16) -> u16 {
+        if self.e_flag && (self.dp & 0xFF) == 0 {
+            self.dp | (base.wrapping_add(index) & 0xFF)
+        } else {
+            self.dp.wrapping_add(base).wrapping_add(index)
+        }
+    }
+
     fn get_effective_address(&mut self, mode: AddressingMode, memory: &mut Memory) -> u32 {
         match mode {
             AddressingMode::DirectPage => {
-                let addr = self.dp + memory.read(self.pc) as u16;
-                self.pc += 1;
-                addr as u32
+                let addr = self.dp.wrapping_add(memory.read(self.full_pc()) as u16);
+                self.advance_pc(1);
+                addr24(0, addr)
             }
 
             AddressingMode::DirectPageIndexedX => {
-                let base = memory.read(self.pc) as u16;
-                self.pc += 1;
-                ((self.dp + base + (self.x & 0xFF)) & 0xFFFF) as u32
+                let base = memory.read(self.full_pc()) as u16;
+                self.advance_pc(1);
+                addr24(0, self.direct_page_indexed(base, self.x & 0xFF))
             }
 
             AddressingMode::DirectPageIndexedY => {
-                let base = memory.read(self.pc) as u16;
-                self.pc += 1;
-                ((self.dp + base + (self.y & 0xFF)) & 0xFFFF) as u32
+                let base = memory.read(self.full_pc()) as u16;
+                self.advance_pc(1);
+                addr24(0, self.direct_page_indexed(base, self.y & 0xFF))
             }
 
             AddressingMode::Absolute => {
-                self.read_address(mode, memory)
+                addr24(self.db, self.read_address(mode, memory) as u16)
             }
 
             AddressingMode::AbsoluteIndexedX => {
-                let base = self.read_address(AddressingMode::Absolute, memory);
-                base + (self.x & 0xFFFF) as u32
+                let base = addr24(self.db, self.read_address(AddressingMode::Absolute, memory) as u16);
+                add_index(base, self.x)
             }
 
             AddressingMode::AbsoluteIndexedY => {
-                let base = self.read_address(AddressingMode::Absolute, memory);
-                base + (self.y & 0xFFFF) as u32
+                let base = addr24(self.db, self.read_address(AddressingMode::Absolute, memory) as u16);
+                add_index(base, self.y)
             }
 
             _ => {
@@ -1209,6 +1537,14 @@ impl Cpu {
         self.p &= !flag;
     }
 
+    fn set_zero_flag(&mut self, set: bool) {
+        if set {
+            self.p |= Self::FLAG_ZERO;
+        } else {
+            self.p &= !Self::FLAG_ZERO;
+        }
+    }
+
     fn set_carry_flag(&mut self, set: bool) {
         if set {
             self.p |= Self::FLAG_CARRY;
@@ -1261,42 +1597,103 @@ impl Cpu {
         (self.p & flag) != 0
     }
 
-    fn update_nz_flags_a(&mut self) {
-        let value = if self.m_flag { self.a & 0xFF } else { self.a };
-        self.update_nz_flags(value);
+    // strict-debug: estados que o hardware não consegue produzir indicam bug do emulador
+    #[cfg(feature = "strict-debug")]
+    fn assert_legal_state(&self, memory: &Memory) {
+        // Opcode já consumido; o PC volta dentro do próprio banco
+        let pc = self.pc_offset(0xFFFF);
+
+        if self.e_flag && (self.sp & 0xFF00) != 0x0100 {
+            panic!("SP fora da página 1 em modo emulação: {:04X} (PC {:06X})", self.sp, pc);
+        }
 
+        if !self.e_flag && (self.m_flag != self.get_flag(0x20) || self.x_flag != self.get_flag(0x10)) {
+            panic!("m_flag/x_flag dessincronizados de P={:02X} (PC {:06X})", self.p, pc);
+        }
+
+        if memory.try_read(pc).is_err() {
+            panic!("PC em região não mapeada: {:06X}", pc);
+        }
     }
 
-    fn update_nz_flags_x(&mut self) {
-        let value = if self.x_flag { self.x & 0xFF } else { self.x };
-        self.update_nz_flags(value);
+    // Larguras efetivas: no modo emulação A e os índices são sempre de 8 bits,
+    // mesmo que m_flag/x_flag tenham sido alterados diretamente
+    pub fn acc_is_8bit(&self) -> bool {
+        self.m_flag || self.e_flag
+    }
 
+    pub fn idx_is_8bit(&self) -> bool {
+        self.x_flag || self.e_flag
+    }
+
+    // A usa a largura de M; X e Y a largura de X
+    fn update_nz_flags_a(&mut self) {
+        self.update_nz_flags_m(self.a);
+    }
+
+    fn update_nz_flags_x(&mut self) {
+        self.update_nz_flags_index(self.x);
     }
 
     fn update_nz_flags_y(&mut self) {
-        let value = if self.x_flag { self.y & 0xFF } else { self.y };
-        self.update_nz_flags(value);
+        self.update_nz_flags_index(self.y);
+    }
+
+    fn update_nz_flags_index(&mut self, value: u16) {
+        if self.idx_is_8bit() {
+            self.update_nz_flags_8(value as u8);
+        } else {
+            self.update_nz_flags_16(value);
+        }
+    }
+
+    // N e Z na largura de M (bit 7 ou bit 15)
+    fn update_nz_flags_m(&mut self, value: u16) {
+        if self.acc_is_8bit() {
+            self.update_nz_flags_8(value as u8);
+        } else {
+            self.update_nz_flags_16(value);
+        }
+    }
+
+    fn update_nz_flags_8(&mut self, value: u8) {
+        self.p &= !(Self::FLAG_ZERO | Self::FLAG_NEGATIVE);
+
+        if value == 0 {
+            self.p |= Self::FLAG_ZERO;
+        }
 
+        if (value & 0x80) != 0 {
+            self.p |= Self::FLAG_NEGATIVE;
+        }
     }
 
-    fn update_nz_flags(&mut self, value: u16) {
+    fn update_nz_flags_16(&mut self, value: u16) {
         self.p &= !(Self::FLAG_ZERO | Self::FLAG_NEGATIVE);
 
         if value == 0 {
             self.p |= Self::FLAG_ZERO;
         }
 
-        let test_bit = if self.m_flag || self.x_flag { 0x80 } else { 0x8000 };
-        if (value & test_bit) != 0 {
+        if (value & 0x8000) != 0 {
             self.p |= Self::FLAG_NEGATIVE;
         }
     }
 
     fn update_mode_flags(&mut self) {
-        if !self.e_flag {
+        if self.e_flag {
+            self.m_flag = true;
+            self.x_flag = true;
+        } else {
             self.m_flag = (self.p & 0x20) != 0;
             self.x_flag = (self.p & 0x10) != 0;
         }
+
+        // Com índices de 8 bits o byte alto de X e Y é sempre zero
+        if self.idx_is_8bit() {
+            self.x &= 0xFF;
+            self.y &= 0xFF;
+        }
     }
 
     fn pull_stack(&mut self, memory: &mut Memory) -> u8  {
@@ -1309,11 +1706,27 @@ impl Cpu {
     pub fn get_register_state(&self) -> String {
         format!(
             "A:{:04X} X:{:04X} Y:{:04X} SP:{:04X} PC:{:06X} DP:{:04X} DB:{:02X} PB:{:02X} P:{:02X} M:{} X:{} E:{}",
-            self.a, self.x, self.y, self.sp, self.pc, self.dp, self.db, self.pb, self.p,
-            if self.m_flag { 8 } else { 16 },
-            if self.x_flag { 8 } else { 16 },
+            self.a, self.x, self.y, self.sp, self.full_pc(), self.dp, self.db, self.pb, self.p,
+            if self.acc_is_8bit() { 8 } else { 16 },
+            if self.idx_is_8bit() { 8 } else { 16 },
             if self.e_flag { "E" } else { "N" }
         )
     }
 
+    // Linha de trace no formato usado em logs de referência: flags em maiúscula quando ligadas
+    pub fn state_line(&self) -> String {
+        let flags: String = "NVMXDIZC"
+            .chars()
+            .enumerate()
+            .map(|(i, letter)| {
+                if (self.p & (0x80 >> i)) != 0 { letter } else { letter.to_ascii_lowercase() }
+            })
+            .collect();
+
+        format!(
+            "{:06X} A:{:04X} X:{:04X} Y:{:04X} SP:{:04X} P:{}",
+            self.full_pc(), self.a, self.x, self.y, self.sp, flags
+        )
+    }
+
 }
@@ -0,0 +1,86 @@
+use crate::system::System;
+
+// Registradores da CPU em um instante, para frontends de depuração
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+    pub a: u16,
+    pub x: u16,
+    pub y: u16,
+    pub sp: u16,
+    pub pc: u32,
+    pub dp: u16,
+    pub db: u8,
+    pub p: u8,
+    pub e_flag: bool,
+}
+
+// Interface de comandos passo a passo sobre um System
+pub struct Debugger {
+    pub system: System,
+}
+
+impl Debugger {
+    pub fn new(system: System) -> Self {
+        Debugger { system }
+    }
+
+    // Executa uma instrução e retorna os ciclos gastos
+    pub fn step_instruction(&mut self) -> u32 {
+        self.system.step()
+    }
+
+    // Executa até a PPU mudar de scanline; retorna as instruções executadas
+    pub fn step_scanline(&mut self) -> usize {
+        let start_line = self.system.get_scanline();
+        let mut instructions = 0;
+
+        while self.system.get_scanline() == start_line {
+            self.system.step();
+            instructions += 1;
+        }
+
+        instructions
+    }
+
+    // Executa até o início do próximo VBlank
+    pub fn step_frame(&mut self) -> usize {
+        let mut instructions = 0;
+
+        loop {
+            let was_vblank = self.system.is_vblank();
+            self.system.step();
+            instructions += 1;
+
+            if !was_vblank && self.system.is_vblank() {
+                break;
+            }
+        }
+
+        instructions
+    }
+
+    // Leitura sem efeitos colaterais em registradores de I/O
+    pub fn read_mem(&self, addr: u32) -> u8 {
+        self.system.memory.peek(addr)
+    }
+
+    pub fn write_mem(&mut self, addr: u32, value: u8) {
+        self.system.memory.write(addr, value);
+    }
+
+    pub fn regs(&self) -> Registers {
+        let cpu = &self.system.cpu;
+
+        Registers {
+            a: cpu.a,
+            x: cpu.x,
+            y: cpu.y,
+            sp: cpu.sp,
+            pc: cpu.full_pc(),
+            dp: cpu.dp,
+            db: cpu.db,
+            p: cpu.p,
+            e_flag: cpu.e_flag,
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::system::System;
+
+// Botões de um controle, na ordem dos bits de JOYxL/JOYxH ($4218-$421F)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    B,
+    Y,
+    Select,
+    Start,
+    Up,
+    Down,
+    Left,
+    Right,
+    A,
+    X,
+    L,
+    R,
+}
+
+impl Button {
+    fn mask(self) -> u16 {
+        let bit = match self {
+            Button::B => 15,
+            Button::Y => 14,
+            Button::Select => 13,
+            Button::Start => 12,
+            Button::Up => 11,
+            Button::Down => 10,
+            Button::Left => 9,
+            Button::Right => 8,
+            Button::A => 7,
+            Button::X => 6,
+            Button::L => 5,
+            Button::R => 4,
+        };
+        1 << bit
+    }
+}
+
+// Botões pressionados em um controle, no formato de JOYxL/JOYxH
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JoypadState {
+    buttons: u16,
+}
+
+impl JoypadState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, button: Button) -> Self {
+        self.set(button, true);
+        self
+    }
+
+    pub fn set(&mut self, button: Button, pressed: bool) {
+        if pressed {
+            self.buttons |= button.mask();
+        } else {
+            self.buttons &= !button.mask();
+        }
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        (self.buttons & button.mask()) != 0
+    }
+
+    pub fn bits(&self) -> u16 {
+        self.buttons
+    }
+}
+
+// Ponto de entrada recomendado: carrega a ROM, parte do vetor de reset e roda frame a frame.
+// Não há saída de áudio: o core não tem DSP, então não existem amostras para entregar ao
+// host. Com a feature `apu` o SPC700 roda junto e entra nos save states
+pub struct Emulator {
+    pub system: System,
+    framebuffer: Vec<u32>,
+    buttons: JoypadState,
+}
+
+impl Emulator {
+    pub fn load(rom: Vec<u8>) -> Self {
+        let mut emulator = Emulator {
+            system: System::new(rom),
+            framebuffer: Vec::new(),
+            buttons: JoypadState::new(),
+        };
+        emulator.reset();
+        emulator
+    }
+
+    // Reset da CPU/PPU com o PC no vetor de reset de modo emulação ($00:FFFC)
+    pub fn reset(&mut self) {
+        self.system.reset();
+
+        let low = self.system.memory.read(0x00FFFC) as u32;
+        let high = self.system.memory.read(0x00FFFD) as u32;
+        self.system.cpu.set_full_pc((high << 8) | low);
+
+        self.write_joypad();
+        self.framebuffer = self.system.get_framebuffer();
+    }
+
+    // Roda até o início do próximo VBlank e devolve o frame desenhado
+    pub fn run_frame(&mut self) -> &[u32] {
+        self.write_joypad();
+        self.system.run_frames_fast(1);
+        self.framebuffer = self.system.get_framebuffer();
+        &self.framebuffer
+    }
+
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        self.buttons.set(button, pressed);
+        self.write_joypad();
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        self.system.save_state()
+    }
+
+    pub fn load_state(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.system.load_state(data)
+    }
+
+    // ROMs com auto-read ligado voltam a latchear o mesmo estado no VBlank
+    fn write_joypad(&mut self) {
+        self.system.set_controller(1, self.buttons);
+    }
+}
@@ -3,8 +3,17 @@ pub mod cpu;
 pub mod opcodes;
 pub mod ppu;
 pub mod system;
+pub mod debug;
+pub mod state;
+pub mod emulator;
+pub mod rewind;
+#[cfg(feature = "apu")]
+pub mod apu;
 
 pub use memory::Memory;
-pub use cpu::Cpu;
-pub use ppu::Ppu;
-pub use system::System;
\ No newline at end of file
+pub use cpu::{Cpu, CpuError};
+pub use ppu::{BgConfig, Layer, Ppu, RegisterShadow, RenderError, SpriteInfo};
+pub use system::{CpuTestState, FrameCallback, FrameProfile, RunStop, System, TimingMode};
+pub use debug::Debugger;
+pub use emulator::{Button, Emulator, JoypadState};
+pub use rewind::Rewind;
\ No newline at end of file
@@ -1,7 +1,11 @@
 use std::collections::HashMap;
+use std::ops::Range;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use crate::ppu::Ppu;
+use crate::state::{self, Reader};
+#[cfg(feature = "apu")]
+use crate::apu::Spc700;
 
 pub struct Memory {
     pub wram: [u8; 0x20000], // 128KB WRAM
@@ -13,20 +17,73 @@ pub struct Memory {
     pub registers: HashMap<u16, u8>, // Registradores(To-DO)
     pub rom_type: RomType, // Tipo de mapeamento (LoRom, HiRom)
     pub sram_size: usize, // Tamanho do SRAM
+    pub sram_dirty: bool, // SRAM alterada desde o último save
+    pub region: Region, // Região de vídeo (NTSC/PAL) do header
+    pub fast_rom: bool, // MEMSEL ($420D): FastROM nos bancos $80-$FF
+    pub joypads: [u16; 4], // Botões dos controles 1-4, lidos pelo auto-read do $4200
+    open_bus: Cell<u8>, // Último byte lido pela CPU (MDR): valor das áreas não mapeadas
+    joypad_shift: Cell<[u16; 2]>, // Registradores seriais de $4016/$4017 (leitura manual)
+    wram_port: Cell<u32>, // Endereço de 17 bits da porta $2180 (WMADD), avança em leituras também
+    dma_stall: u32, // Ciclos de CPU gastos em DMA ainda não cobrados
+    flat: Option<Vec<u8>>, // Espaço plano de 16MB (suítes de teste de CPU)
+    dma_log: Option<Vec<DmaTransfer>>, // Log de transferências, desligado por padrão
+    io_handlers: Vec<(Range<u32>, IoSlot)>, // Consultados antes da decodificação
+    #[cfg(feature = "apu")]
+    pub apu: Spc700, // SPC700 por trás das portas $2140-$2143
 
     ppu: Rc<RefCell<Ppu>>,
 }
 
-#[derive(Debug, Clone, Copy)]
+// Periférico mapeado em memória instalado com Memory::map_io
+pub trait IoHandler {
+    fn read(&mut self, addr: u32) -> u8;
+    fn write(&mut self, addr: u32, value: u8);
+}
+
+// Uma transferência de DMA registrada pelo log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmaTransfer {
+    pub channel: u8,
+    pub source: u32,      // Endereço inicial no barramento A
+    pub destination: u16, // Registrador do barramento B ($21xx)
+    pub length: u32,
+    pub scanline: u16,
+}
+
+// Erros de acesso para ferramentas que precisam distinguir memória mapeada de open bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemError {
+    Unmapped(u32),
+}
+
+type IoSlot = RefCell<Box<dyn IoHandler>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RomType {
     LoRom,
     HiRom,
+    ExHiRom,
+}
+
+// Área varrida por Memory::search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchRegion {
+    Wram,
+    Sram,
+    Rom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Ntsc,
+    Pal,
 }
 
 impl Memory{
     pub fn new(rom: Vec<u8>, ppu: Rc<RefCell<Ppu>>) -> Self {
         let rom_type = Self::detect_rom_type(&rom);
-        let sram_size = Self::detect_sram_size(&rom);
+        let sram_size = Self::detect_sram_size(&rom, rom_type);
+        let region = Self::region_from_header(&rom, rom_type);
 
         Memory {
             wram: [0; 0x20000],
@@ -38,10 +95,34 @@ impl Memory{
             registers: HashMap::new(),
             rom_type,
             sram_size,
+            sram_dirty: false,
+            region,
+            fast_rom: false,
+            joypads: [0; 4],
+            open_bus: Cell::new(0),
+            joypad_shift: Cell::new([0; 2]),
+            wram_port: Cell::new(0),
+            dma_stall: 0,
+            flat: None,
+            dma_log: None,
+            io_handlers: Vec::new(),
+            #[cfg(feature = "apu")]
+            apu: Spc700::new(),
             ppu,
         }
     }
 
+    // Espaço de 24 bits plano, sem cartucho nem registradores: para suítes de teste de CPU
+    pub fn flat() -> Self {
+        Self::flat_with_ppu(Rc::new(RefCell::new(Ppu::new())))
+    }
+
+    pub(crate) fn flat_with_ppu(ppu: Rc<RefCell<Ppu>>) -> Self {
+        let mut memory = Self::new(Vec::new(), ppu);
+        memory.flat = Some(vec![0; 0x1000000]);
+        memory
+    }
+
     fn detect_rom_type(rom: &[u8]) -> RomType {
         if rom.len() < 0x8000 {
             return RomType::LoRom; // ROM muito pequena para ser HiRom
@@ -71,12 +152,44 @@ impl Memory{
         RomType::LoRom // Padrão para LoRom
     }
 
-    fn detect_sram_size(rom: &[u8]) -> usize {
-        if rom.len() < 0x7FD8 {
+    // Força um mapeamento conhecido, ignorando a detecção pelo header
+    pub fn set_rom_type(&mut self, rom_type: RomType) {
+        self.rom_type = rom_type;
+        self.sram_size = Self::detect_sram_size(&self.rom, rom_type);
+        self.sram.resize(self.sram_size, 0);
+        self.region = Self::region_from_header(&self.rom, rom_type);
+    }
+
+    fn header_offset(rom_type: RomType) -> usize {
+        match rom_type {
+            RomType::LoRom => 0x7FC0,
+            RomType::HiRom => 0xFFC0,
+            RomType::ExHiRom => 0x40FFC0,
+        }
+    }
+
+    // Região pelo header da ROM carregada, sem considerar overrides
+    pub fn detect_region(&self) -> Region {
+        Self::region_from_header(&self.rom, self.rom_type)
+    }
+
+    // Código de país do header ($FFD9): Europa e Austrália usam PAL
+    fn region_from_header(rom: &[u8], rom_type: RomType) -> Region {
+        let country_offset = Self::header_offset(rom_type) + 0x19;
+
+        match rom.get(country_offset) {
+            Some(0x02..=0x0C | 0x11) => Region::Pal,
+            _ => Region::Ntsc,
+        }
+    }
+
+    fn detect_sram_size(rom: &[u8], rom_type: RomType) -> usize {
+        let sram_offset = Self::header_offset(rom_type) + 0x18;
+        if rom.len() <= sram_offset {
             return 0;
         }
 
-        let sram_byte = rom[0x7FD8];
+        let sram_byte = rom[sram_offset];
         match sram_byte {
             0x00 => 0, // sem SRAM
             0x01 => 0x800, // 2KB
@@ -87,7 +200,28 @@ impl Memory{
         }
     }
 
+    // Cada byte lido fica no barramento: leituras de 16 bits que caem em área não
+    // mapeada repetem o byte anterior, não zero
     pub fn read(&self, addr: u32) -> u8 {
+        let value = self.read_bus(addr);
+        self.open_bus.set(value);
+        value
+    }
+
+    // Valor atual do open bus da CPU
+    pub fn open_bus(&self) -> u8 {
+        self.open_bus.get()
+    }
+
+    fn read_bus(&self, addr: u32) -> u8 {
+        if let Some(handler) = self.io_handler(addr) {
+            return handler.borrow_mut().read(addr);
+        }
+
+        if let Some(flat) = &self.flat {
+            return flat[(addr & 0xFFFFFF) as usize];
+        }
+
         let bank = (addr >> 16) as u8;
         let offset = (addr & 0xFFFF) as u16;
 
@@ -100,7 +234,7 @@ impl Memory{
                     0x2100..=0x21FF => self.read_ppu_registers(offset),
                     0x2200..=0x3FFF => self.wram[offset as usize],
                     0x4000..=0x4015 => self.read_apu_registers(offset),
-                    0x4016..=0x4017 => self.registers.get(&offset).copied().unwrap_or(0), // Input
+                    0x4016..=0x4017 => self.read_joypad_serial(offset), // Input
                     0x4018..=0x401F => self.read_apu_registers(offset),
                     0x4020..=0x41FF => self.read_apu_registers(offset),
                     0x4210..=0x4212 => self.read_ppu_registers(offset),
@@ -112,34 +246,23 @@ impl Memory{
                             if sram_addr < self.sram.len(){
                                 self.sram[sram_addr]
                             } else {
-                                0
+                                self.open_bus.get()
                             }
                         } else {
-                            0
+                            self.open_bus.get()
                         }
                     }
                     //LoRom Area
-                    0x8000..=0xFFFF => {
-                        let rom_addr = ((bank as usize) * 0x8000) + ((offset - 0x8000) as usize);
-                        if rom_addr < self.rom.len() {
-                            self.rom[rom_addr]
-                        } else {
-                            0
-                        }
-                    }
+                    0x8000..=0xFFFF => self.read_rom(bank, offset),
                 }
             }
 
             0x40..=0x6F => {
-                if offset >= 0x8000 {
-                    let rom_addr = ((bank as usize) * 0x8000) + ((offset - 0x8000) as usize);
-                    if rom_addr < self.rom.len(){
-                        self.rom[rom_addr]
-                    } else {
-                        0
+                match (self.rom_type, offset) {
+                    (RomType::HiRom | RomType::ExHiRom, _) | (RomType::LoRom, 0x8000..=0xFFFF) => {
+                        self.read_rom(bank, offset)
                     }
-                } else {
-                    0 // Areas não mapeadas
+                    _ => self.open_bus.get() // Areas não mapeadas
                 }
             }
 
@@ -154,7 +277,7 @@ impl Memory{
                     0x2100..=0x21FF => self.read_ppu_registers(offset),
                     0x2200..=0x3FFF => self.wram[offset as usize],
                     0x4000..=0x4015 => self.read_apu_registers(offset),
-                    0x4016..=0x4017 => self.registers.get(&offset).copied().unwrap_or(0), // Input
+                    0x4016..=0x4017 => self.read_joypad_serial(offset), // Input
                     0x4018..=0x401F => self.read_apu_registers(offset),
                     0x4020..=0x41FF => self.read_apu_registers(offset),
                     0x4210..=0x4212 => self.read_ppu_registers(offset),
@@ -166,47 +289,206 @@ impl Memory{
                             if sram_addr < self.sram.len(){
                                 self.sram[sram_addr]
                             } else {
-                                0
+                                self.open_bus.get()
                             }
                         } else {
-                            0
-                        }
-                    }
-                    0x8000..=0xFFFF => {
-                        let mapped_bank = bank - 0x80;
-                        let rom_addr = ((mapped_bank as usize) * 0x8000) + ((offset - 0x8000) as usize);
-                        if rom_addr < self.rom.len() {
-                            self.rom[rom_addr]
-                        } else {
-                            0
+                            self.open_bus.get()
                         }
                     }
+                    0x8000..=0xFFFF => self.read_rom(bank, offset),
                 }
             }
 
             //HiRom area ou continuação do LoRom
             0xC0..=0xFF => {
                 match self.rom_type {
-                    RomType::HiRom => {
-                        let rom_addr = (((bank - 0xC0) as usize) << 16) | (offset as usize);
-                        if rom_addr < self.rom.len() {
-                            self.rom[rom_addr]
-                        } else {
-                            0
-                        }
-                    }
+                    RomType::HiRom | RomType::ExHiRom => self.read_rom(bank, offset),
                     RomType::LoRom => {
                         //unmapped area
-                        0
+                        self.open_bus.get()
                     }
                 }
             }
 
-            _ => 0, // Unmapped area
+            _ => self.open_bus.get(), // Unmapped area
+        }
+    }
+
+    // Leitura que separa endereços sem nada por trás (erro) de leituras válidas
+    pub fn try_read(&self, addr: u32) -> Result<u8, MemError> {
+        let addr = addr & 0xFFFFFF;
+        if self.is_mapped(addr) {
+            Ok(self.read_bus(addr))
+        } else {
+            Err(MemError::Unmapped(addr))
+        }
+    }
+
+    // Mesma decodificação de read: SRAM ausente, ROM curta e bancos livres não são mapeados
+    fn is_mapped(&self, addr: u32) -> bool {
+        if self.flat.is_some() || self.io_handler(addr).is_some() {
+            return true;
+        }
+
+        let bank = (addr >> 16) as u8;
+        let offset = (addr & 0xFFFF) as u16;
+        let rom_backed = |bank, offset| self.rom_index(bank, offset) < self.rom.len();
+
+        match bank {
+            0x00..=0x3F | 0x80..=0xBF => match offset {
+                0x6000..=0x7FFF => ((offset - 0x6000) as usize) < self.sram.len(),
+                0x8000..=0xFFFF => rom_backed(bank, offset),
+                _ => true,
+            },
+
+            0x40..=0x6F => match (self.rom_type, offset) {
+                (RomType::HiRom | RomType::ExHiRom, _) | (RomType::LoRom, 0x8000..=0xFFFF) => {
+                    rom_backed(bank, offset)
+                }
+                _ => false,
+            },
+
+            0x7E | 0x7F => true,
+
+            0xC0..=0xFF => self.rom_type != RomType::LoRom && rom_backed(bank, offset),
+
+            _ => false,
+        }
+    }
+
+    // Instala um handler para um intervalo de endereços de 24 bits; o último instalado tem prioridade
+    pub fn map_io(&mut self, range: Range<u32>, handler: Box<dyn IoHandler>) {
+        self.io_handlers.push((range, RefCell::new(handler)));
+    }
+
+    fn io_handler(&self, addr: u32) -> Option<&IoSlot> {
+        let addr = addr & 0xFFFFFF;
+        self.io_handlers
+            .iter()
+            .rev()
+            .find(|(range, _)| range.contains(&addr))
+            .map(|(_, handler)| handler)
+    }
+
+    // Leitura sem efeitos colaterais: registradores de I/O retornam o último valor escrito
+    // em vez de avançar ponteiros de VRAM/OAM/CGRAM ou limpar flags da PPU
+    pub fn peek(&self, addr: u32) -> u8 {
+        let bank = (addr >> 16) as u8;
+        let offset = (addr & 0xFFFF) as u16;
+
+        match (bank, offset) {
+            (0x00..=0x3F | 0x80..=0xBF, 0x2100..=0x21FF | 0x4210..=0x4212) if self.flat.is_none() => {
+                self.registers.get(&offset).copied().unwrap_or(0)
+            }
+            (0x00..=0x3F | 0x80..=0xBF, 0x4016..=0x4017) if self.flat.is_none() => {
+                (self.joypad_shift.get()[(offset - 0x4016) as usize] >> 15) as u8
+            }
+            _ => self.read_bus(addr),
+        }
+    }
+
+    pub fn dump_region(&self, start: u32, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| self.peek(start.wrapping_add(i as u32) & 0xFFFFFF))
+            .collect()
+    }
+
+    // Estado de I/O para save states: registradores (inclui os canais de DMA em $43xx),
+    // portas da WRAM e dos controles, MDR e ciclos de DMA pendentes
+    pub(crate) fn save_io(&self, out: &mut Vec<u8>) {
+        let mut registers: Vec<_> = self.registers.iter().collect();
+        registers.sort();
+
+        out.extend_from_slice(&(registers.len() as u16).to_le_bytes());
+        for (&addr, &value) in registers {
+            out.extend_from_slice(&addr.to_le_bytes());
+            out.push(value);
         }
+
+        state::write_words(out, &self.joypads);
+        state::write_words(out, &self.joypad_shift.get());
+        out.extend_from_slice(&self.wram_port.get().to_le_bytes());
+        out.extend_from_slice(&self.dma_stall.to_le_bytes());
+        out.extend_from_slice(&[self.open_bus.get(), self.fast_rom as u8]);
+    }
+
+    // Inverso de save_io; nada muda se a seção estiver truncada
+    pub(crate) fn load_io(&mut self, reader: &mut Reader) -> Option<()> {
+        let count = reader.u16()?;
+        let mut registers = HashMap::with_capacity(count as usize);
+        for _ in 0..count {
+            registers.insert(reader.u16()?, reader.u8()?);
+        }
+
+        let joypads = reader.words()?;
+        let joypad_shift = reader.words()?;
+        let wram_port = reader.u32()?;
+        let dma_stall = reader.u32()?;
+        let [open_bus, fast_rom] = reader.bytes()?;
+
+        self.registers = registers;
+        self.joypads = joypads;
+        self.joypad_shift.set(joypad_shift);
+        self.wram_port.set(wram_port & 0x1FFFF);
+        self.dma_stall = dma_stall;
+        self.open_bus.set(open_bus);
+        self.fast_rom = fast_rom != 0;
+        Some(())
+    }
+
+    // Memória de vídeo crua para visualizadores de tiles e paletas
+    pub fn export_vram(&self) -> &[u8] {
+        &self.vram
+    }
+
+    pub fn export_cgram(&self) -> &[u8] {
+        &self.cgram
+    }
+
+    pub fn export_oam(&self) -> &[u8] {
+        &self.oam
+    }
+
+    // Procura um padrão de bytes. WRAM retorna endereços a partir de $7E:0000;
+    // SRAM e ROM retornam offsets dentro dos próprios buffers
+    pub fn search(&self, value: &[u8], region: SearchRegion) -> Vec<u32> {
+        let (data, base): (&[u8], u32) = match region {
+            SearchRegion::Wram => (&self.wram, 0x7E0000),
+            SearchRegion::Sram => (&self.sram, 0),
+            SearchRegion::Rom => (&self.rom, 0),
+        };
+
+        if value.is_empty() || value.len() > data.len() {
+            return Vec::new();
+        }
+
+        data.windows(value.len())
+            .enumerate()
+            .filter(|(_, window)| *window == value)
+            .map(|(i, _)| base + i as u32)
+            .collect()
+    }
+
+    pub fn search_u8(&self, value: u8, region: SearchRegion) -> Vec<u32> {
+        self.search(&[value], region)
+    }
+
+    // Valores de 16 bits em little-endian, como a CPU os grava
+    pub fn search_u16(&self, value: u16, region: SearchRegion) -> Vec<u32> {
+        self.search(&value.to_le_bytes(), region)
     }
 
     pub fn write(&mut self, addr: u32, value: u8) {
+        if let Some(handler) = self.io_handler(addr) {
+            handler.borrow_mut().write(addr, value);
+            return;
+        }
+
+        if let Some(flat) = &mut self.flat {
+            flat[(addr & 0xFFFFFF) as usize] = value;
+            return;
+        }
+
         let bank = (addr >> 16) as u8;
         let offset = (addr & 0xFFFF) as u16;
 
@@ -219,7 +501,7 @@ impl Memory{
                     0x2100..=0x21FF => self.write_ppu_registers(offset, value),
                     0x2200..=0x3FFF => self.wram[offset as usize] = value,
                     0x4000..=0x4015 => self.write_apu_registers(offset, value),
-                    0x4016..=0x4017 => { self.registers.insert(offset, value); }, // Input
+                    0x4016..=0x4017 => self.write_joypad_strobe(offset, value), // Input
                     0x4018..=0x401F => self.write_apu_registers(offset, value),
                     0x4020..=0x41FF => self.write_apu_registers(offset, value),
                     0x4200..=0x44FF => self.write_dma_registers(offset, value),
@@ -229,6 +511,7 @@ impl Memory{
                             let sram_addr = (offset - 0x6000) as usize;
                             if sram_addr < self.sram.len() {
                                 self.sram[sram_addr] = value;
+                                self.sram_dirty = true;
                             }
                         }
                     } // Input
@@ -246,7 +529,7 @@ impl Memory{
                     0x2100..=0x21FF => self.write_ppu_registers(offset, value),
                     0x2200..=0x3FFF => self.wram[offset as usize] = value,
                     0x4000..=0x4015 => self.write_apu_registers(offset, value),
-                    0x4016..=0x4017 => { self.registers.insert(offset, value); }, // Input
+                    0x4016..=0x4017 => self.write_joypad_strobe(offset, value), // Input
                     0x4018..=0x401F => self.write_apu_registers(offset, value),
                     0x4020..=0x41FF => self.write_apu_registers(offset, value),
                     0x4200..=0x44FF => self.write_dma_registers(offset, value),
@@ -256,6 +539,7 @@ impl Memory{
                             let sram_addr = (offset - 0x6000) as usize;
                             if sram_addr < self.sram.len() {
                                 self.sram[sram_addr] = value;
+                                self.sram_dirty = true;
                             }
                         }
                     }
@@ -270,30 +554,35 @@ impl Memory{
     fn read_ppu_registers(&self, addr: u16) -> u8 {
         let mut ppu = self.ppu.borrow_mut();
 
-        match addr {
+        let value = match addr {
             0x213C | 0x213D | 0x213E | 0x213F | 0x4210 | 0x4211 |0x4212 => {
                 ppu.read_register(addr)
             } 
 
+            // Com o acesso bloqueado as portas de leitura devolvem lixo (open bus da PPU,
+            // que continua dirigindo o barramento, ou o buffer antigo), mas os endereços
+            // continuam avançando
+            // Mesmo endereçamento de $2104: 10 bits, tabela alta espelhada a cada 32 bytes
             0x2138 => {
-                let oam_addr = ppu.oam_addr;
-                if (oam_addr as usize) < self.oam.len() {
-                    let value = self.oam[oam_addr as usize];
-                    ppu.oam_addr = ppu.oam_addr.wrapping_add(1);
-                    value
+                let oam_addr = (ppu.oam_addr & 0x3FF) as usize;
+                let value = if ppu.memory_access_blocked() {
+                    ppu.open_bus
+                } else if oam_addr < 0x200 {
+                    self.oam[oam_addr]
                 } else {
-                    0
-                }
+                    self.oam[0x200 + (oam_addr & 0x1F)]
+                };
+
+                ppu.oam_addr = ppu.oam_addr.wrapping_add(1) & 0x3FF;
+                value
             }
 
             0x2139 => {
                 let value = (ppu.vram_read_buffer & 0xFF) as u8;
 
                 if (ppu.vmain & 0x80) == 0 {
-                    let vram_addr = ppu.vram_addr as usize;
-                    if vram_addr * 2 + 1 < self.vram.len() {
-                        ppu.vram_read_buffer = (self.vram[vram_addr * 2 + 1] as u16) << 8 |
-                                                (self.vram[vram_addr * 2] as u16);
+                    if !ppu.memory_access_blocked() {
+                        ppu.vram_read_buffer = self.vram_word(ppu.vram_addr);
                     }
                     ppu.vram_addr = ppu.vram_addr.wrapping_add(ppu.vram_increment);
                 }
@@ -303,12 +592,10 @@ impl Memory{
 
             0x213A => {
                 let value = (ppu.vram_read_buffer >> 8) as u8;
-                
+
                 if (ppu.vmain & 0x80) != 0 {
-                    let vram_addr = ppu.vram_addr as usize;
-                    if vram_addr * 2 + 1 < self.vram.len() {
-                        ppu.vram_read_buffer = (self.vram[vram_addr * 2 + 1] as u16) << 8 |
-                                                (self.vram[vram_addr * 2] as u16);
+                    if !ppu.memory_access_blocked() {
+                        ppu.vram_read_buffer = self.vram_word(ppu.vram_addr);
                     }
                     ppu.vram_addr = ppu.vram_addr.wrapping_add(ppu.vram_increment);
                 }
@@ -319,7 +606,11 @@ impl Memory{
             0x213B => {
                 let cgram_addr = ppu.cgram_addr as usize;
                 if cgram_addr < self.cgram.len() {
-                    let value = self.cgram[cgram_addr as usize];
+                    let value = if ppu.memory_access_blocked() {
+                        ppu.open_bus
+                    } else {
+                        self.cgram[cgram_addr]
+                    };
                     ppu.cgram_addr = (ppu.cgram_addr + 1) & 0x1FF;
                     value
                 } else {
@@ -327,37 +618,42 @@ impl Memory{
                 }
             }
 
-            0x2134 | 0x2135 | 0x2136 => {
-                ppu.open_bus
+            // Resultado da multiplicação ainda não emulado: fica o MDR da CPU
+            0x2134..=0x2136 => {
+                self.open_bus.get()
             }
 
-            0x2140..=0x2143 => {
-                self.registers.get(&addr).copied().unwrap_or(0) // APU Ports - Placeholder
+            // As 4 portas do APU se repetem em $2140-$217F
+            #[cfg(feature = "apu")]
+            0x2140..=0x217F => {
+                self.apu.read_port(addr)
             }
 
-            0x2180 => {
-                let wram_addr = self.registers.get(&0x2181).copied().unwrap_or(0) as usize |
-                                (self.registers.get(&0x2182).copied().unwrap_or(0) as usize) << 8 |
-                                ((self.registers.get(&0x2183).copied().unwrap_or(0) as usize) & 0x01) << 16;
-
-                if wram_addr < self.wram.len() {
-                    self.wram[wram_addr]
-
-                } else {
-                    0
-                }
+            #[cfg(not(feature = "apu"))]
+            0x2140..=0x217F => {
+                self.registers.get(&(0x2140 | (addr & 0x03))).copied().unwrap_or(0) // APU Ports - Placeholder
             }
 
-            0x2181..=0x2183 => {
-                self.registers.get(&addr).copied().unwrap_or(0)
+            0x2180 => {
+                let wram_addr = self.next_wram_port_addr();
+                self.wram[wram_addr as usize]
             }
 
+            // Registradores write-only não dirigem o barramento: a leitura devolve o
+            // MDR da CPU, não o último valor escrito
             0x2100..=0x21FF => {
-                ppu.open_bus //placeholder
+                self.open_bus.get()
             }
             
             _ => self.registers.get(&addr).copied().unwrap_or(0), // Outros registradores PPU
+        };
+
+        // Só leituras dos registradores legíveis ($2134-$213F) atualizam o open bus
+        if let 0x2134..=0x213F = addr {
+            ppu.open_bus = value;
         }
+
+        value
     }
 
     fn write_ppu_registers(&mut self, addr: u16, value: u8) {
@@ -367,51 +663,88 @@ impl Memory{
             0x2100 => ppu.write_register(addr, value),
             0x2101 => ppu.write_register(addr, value),
             0x2105 => ppu.write_register(addr, value),
+            0x2106 => ppu.write_register(addr, value),
             0x212C => ppu.write_register(addr, value),
-            0x4200 => ppu.write_register(addr, value),
-
+            0x2132 => ppu.write_register(addr, value),
+            0x212D => ppu.write_register(addr, value),
+            0x2130 => ppu.write_register(addr, value),
+            0x2133 => ppu.write_register(addr, value),
+            0x2107..=0x210C => ppu.write_register(addr, value),
+            0x2115 => ppu.write_register(addr, value),
+
+            // Mudar o endereço recarrega o buffer de leitura com a word do novo endereço
             0x2116 => {
                 self.registers.insert(addr, value);
                 ppu.vram_addr = (ppu.vram_addr & 0xFF00) | (value as u16);
+                if !ppu.memory_access_blocked() {
+                    ppu.vram_read_buffer = self.vram_word(ppu.vram_addr);
+                }
             }
 
             0x2117 => {
                 self.registers.insert(addr, value);
                 ppu.vram_addr = (ppu.vram_addr & 0x00FF) | ((value as u16) << 8);
+                if !ppu.memory_access_blocked() {
+                    ppu.vram_read_buffer = self.vram_word(ppu.vram_addr);
+                }
             }
 
+            // VRAM é endereçada em words: $2118 escreve o byte baixo e $2119 o alto.
+            // O bit 7 do VMAIN escolhe qual das duas escritas incrementa o endereço.
+            // Escritas bloqueadas (modo estrito) são descartadas, mas o endereço avança
             0x2118 => {
-                let vram_addr = ppu.vram_addr;
-                if (vram_addr as usize) < self.vram.len(){
-                    self.vram[vram_addr as usize] = value;
+                let vram_addr = (ppu.vram_addr & 0x7FFF) as usize;
+                if !ppu.memory_access_blocked() {
+                    self.vram[vram_addr * 2] = value;
+                }
+
+                if (ppu.vmain & 0x80) == 0 {
+                    ppu.vram_addr = ppu.vram_addr.wrapping_add(ppu.vram_increment);
                 }
-                ppu.vram_addr = ppu.vram_addr.wrapping_add(ppu.vram_increment);
             }
 
             0x2119 => {
-                let vram_addr = ppu.vram_addr;
-                if (vram_addr as usize) < self.vram.len(){
-                    self.vram[(vram_addr + 1) as usize ] = value;
+                let vram_addr = (ppu.vram_addr & 0x7FFF) as usize;
+                if !ppu.memory_access_blocked() {
+                    self.vram[vram_addr * 2 + 1] = value;
                 }
-                ppu.vram_addr = ppu.vram_addr.wrapping_add(ppu.vram_increment);
-            }
 
-            0x2102 => {
-                self.registers.insert(0x2102, value);
-                ppu.oam_addr = (ppu.oam_addr & 0xFF00) | (value as u16);
+                if (ppu.vmain & 0x80) != 0 {
+                    ppu.vram_addr = ppu.vram_addr.wrapping_add(ppu.vram_increment);
+                }
             }
 
-            0x2103 => {
-                self.registers.insert(0x2103, value);
-                ppu.oam_addr = (ppu.oam_addr & 0x00FF) | ((value as u16) << 8);
+            // OAMADD é endereço de word: $2102 = bits 0-7, bit 0 de $2103 = bit 8,
+            // bit 7 de $2103 = rotação de prioridade. Endereço em bytes = word << 1
+            0x2102 | 0x2103 => {
+                self.registers.insert(addr, value);
+                let low = self.registers.get(&0x2102).copied().unwrap_or(0) as u16;
+                let high = self.registers.get(&0x2103).copied().unwrap_or(0);
+
+                ppu.oam_reload = (((high as u16 & 0x01) << 8) | low) << 1;
+                ppu.oam_priority = (high & 0x80) != 0;
+                ppu.oam_addr = ppu.oam_reload;
             }
 
             0x2104 => {
-                let oam_addr = ppu.oam_addr;
-                if (oam_addr as usize) < self.oam.len() {
-                    self.oam[oam_addr as usize] = value;
+                let oam_addr = (ppu.oam_addr & 0x3FF) as usize;
+
+                let blocked = ppu.memory_access_blocked();
+
+                if oam_addr < 0x200 {
+                    // Tabela baixa: byte par fica no latch, o ímpar grava o par inteiro
+                    if oam_addr & 1 == 0 {
+                        ppu.oam_latch = value;
+                    } else if !blocked {
+                        self.oam[oam_addr - 1] = ppu.oam_latch;
+                        self.oam[oam_addr] = value;
+                    }
+                } else if !blocked {
+                    // Tabela alta (32 bytes, espelhada) é gravada direto
+                    self.oam[0x200 + (oam_addr & 0x1F)] = value;
                 }
-                ppu.oam_addr = ppu.oam_addr.wrapping_add(1);
+
+                ppu.oam_addr = ppu.oam_addr.wrapping_add(1) & 0x3FF;
             }
 
             0x2121 => {
@@ -420,7 +753,7 @@ impl Memory{
 
             0x2122 => {
                 let cgram_addr = ppu.cgram_addr;
-                if (cgram_addr as usize) < self.cgram.len() {
+                if (cgram_addr as usize) < self.cgram.len() && !ppu.memory_access_blocked() {
                     self.cgram[cgram_addr as usize] = value;
                 }
                 ppu.cgram_addr = ppu.cgram_addr.wrapping_add(1);
@@ -442,31 +775,36 @@ impl Memory{
                 ppu.bg_vscroll[1] = (ppu.bg_vscroll[1] & 0xFF00) | (value as u16);
             }
 
-            0x2140..=0x2143 => {
-                self.registers.insert(addr, value); // APU Ports - Placeholder
+            // As 4 portas do APU se repetem em $2140-$217F
+            #[cfg(feature = "apu")]
+            0x2140..=0x217F => {
+                self.apu.write_port(addr, value);
             }
 
-            0x2180 => {
-                let wram_addr = self.registers.get(&0x2181).copied().unwrap_or(0) as usize |
-                                (self.registers.get(&0x2182).copied().unwrap_or(0) as usize) << 8 |
-                                ((self.registers.get(&0x2183).copied().unwrap_or(0) as usize) & 0x01) << 16;
+            #[cfg(not(feature = "apu"))]
+            0x2140..=0x217F => {
+                self.registers.insert(0x2140 | (addr & 0x03), value); // APU Ports - Placeholder
+            }
 
-                if wram_addr < self.wram.len() {
-                    self.wram[wram_addr] = value;
-                }
+            0x2180 => {
+                let wram_addr = self.next_wram_port_addr();
+                self.wram[wram_addr as usize] = value;
+            }
 
-                let new_addr = (wram_addr + 1) & 0x1FFFF;
-                self.registers.insert(0x2181, (new_addr & 0xFF) as u8);
-                self.registers.insert(0x2182, ((new_addr >> 8) & 0xFF) as u8);
-                self.registers.insert(0x2183, ((new_addr >> 16) & 0x01) as u8);
+            // WMADD: 17 bits, só o bit 0 de $2183 conta
+            0x2181 => {
+                self.registers.insert(addr, value);
+                self.wram_port.set((self.wram_port.get() & 0x1FF00) | value as u32);
             }
 
-            0x2181..=0x2182 => {
+            0x2182 => {
                 self.registers.insert(addr, value);
+                self.wram_port.set((self.wram_port.get() & 0x100FF) | (value as u32) << 8);
             }
 
             0x2183 => {
                 self.registers.insert(addr, value & 0x01);
+                self.wram_port.set((self.wram_port.get() & 0x0FFFF) | ((value & 0x01) as u32) << 16);
             }
 
             _ => { self.registers.insert(addr, value); }
@@ -489,12 +827,30 @@ impl Memory{
         Ok(())
     }
 
+    // Converte banco:offset no índice da ROM conforme o mapeamento
+    fn rom_index(&self, bank: u8, offset: u16) -> usize {
+        match self.rom_type {
+            RomType::LoRom => ((bank & 0x7F) as usize) * 0x8000 + ((offset & 0x7FFF) as usize),
+            RomType::HiRom => (((bank & 0x3F) as usize) << 16) | (offset as usize),
+            RomType::ExHiRom => {
+                // Bancos C0-FF/80-BF: primeiros 4MB; bancos 40-7D/00-3F: restante
+                let upper = if bank & 0x80 == 0 { 0x400000 } else { 0 };
+                upper | (((bank & 0x3F) as usize) << 16) | (offset as usize)
+            }
+        }
+    }
+
+    fn read_rom(&self, bank: u8, offset: u16) -> u8 {
+        self.rom.get(self.rom_index(bank, offset)).copied().unwrap_or_else(|| self.open_bus.get())
+    }
+
     pub fn get_rom_title(&self) -> String {
-        if self.rom.len() < 0x7FC0 + 21 {
+        let header = Self::header_offset(self.rom_type);
+        if self.rom.len() < header + 21 {
             return "Unknown".to_string();
         }
 
-        let title_bytes = &self.rom[0x7FC0..0x7FC0 + 21];
+        let title_bytes = &self.rom[header..header + 21];
         String::from_utf8_lossy(title_bytes).trim().to_string()
     }
 
@@ -509,14 +865,198 @@ impl Memory{
 
     // DMA/HDMA Registers ($4200-$44FF)
     fn read_dma_registers(&self, addr: u16) -> u8 {
-        self.registers.get(&addr).copied().unwrap_or(0)
+        match addr {
+            // RDNMI, TIMEUP e HVBJOY vêm do estado da PPU
+            0x4210..=0x4212 => self.ppu.borrow_mut().read_register(addr),
+            _ => self.registers.get(&addr).copied().unwrap_or(0),
+        }
     }
 
     fn write_dma_registers(&mut self, addr: u16, value: u8) {
         self.registers.insert(addr, value);
+
+        match addr {
+            0x4200 => self.ppu.borrow_mut().write_register(addr, value),
+            0x420B => self.run_dma(value),
+            0x420D => self.fast_rom = (value & 0x01) != 0,
+            _ => {}
+        }
+    }
+
+    // Endereço atual da porta $2180; o seguinte dá wrap em 128KB
+    fn next_wram_port_addr(&self) -> u32 {
+        let addr = self.wram_port.get();
+        self.wram_port.set((addr + 1) & 0x1FFFF);
+        addr
+    }
+
+    // Auto-read do início do VBlank: latch dos controles em JOY1-JOY4 ($4218-$421F)
+    pub fn auto_read_joypads(&mut self) {
+        for (port, &buttons) in self.joypads.iter().enumerate() {
+            let addr = 0x4218 + port as u16 * 2;
+            self.registers.insert(addr, buttons as u8);
+            self.registers.insert(addr + 1, (buttons >> 8) as u8);
+        }
+    }
+
+    // Leitura manual: escrever 1 no bit 0 de $4016 recarrega os registradores seriais
+    // dos controles 1 e 2; cada leitura de $4016/$4017 devolve um botão, do B ao R
+    fn write_joypad_strobe(&mut self, offset: u16, value: u8) {
+        self.registers.insert(offset, value);
+        if offset == 0x4016 && (value & 0x01) != 0 {
+            self.joypad_shift.set([self.joypads[0], self.joypads[1]]);
+        }
+    }
+
+    fn read_joypad_serial(&self, offset: u16) -> u8 {
+        let port = (offset - 0x4016) as usize;
+
+        // Com o strobe ligado o registrador é recarregado a cada leitura e devolve sempre o B
+        if self.registers.get(&0x4016).is_some_and(|&strobe| (strobe & 0x01) != 0) {
+            return (self.joypads[port] >> 15) as u8;
+        }
+
+        // Depois dos 16 botões o registrador devolve 1
+        let mut shift = self.joypad_shift.get();
+        let bit = (shift[port] >> 15) as u8;
+        shift[port] = (shift[port] << 1) | 1;
+        self.joypad_shift.set(shift);
+        bit
+    }
+
+    // Stall de DMA em ciclos de CPU (8 master cycles cada): 2 de overhead por transferência
+    // (~12-24 master cycles no hardware) e 1 por canal (8 master cycles)
+    pub const DMA_OVERHEAD_CYCLES: u32 = 2;
+    pub const DMA_CHANNEL_CYCLES: u32 = 1;
+
+    // Devolve e zera os ciclos de stall acumulados pelos DMAs
+    pub fn take_dma_stall(&mut self) -> u32 {
+        std::mem::take(&mut self.dma_stall)
+    }
+
+    // Master cycles de um acesso ao barramento: 6 (rápido), 8 (lento) ou 12 (joypad)
+    pub fn access_cycles(&self, addr: u32) -> u32 {
+        let bank = (addr >> 16) as u8;
+        let offset = addr as u16;
+        let rom_cycles = if self.fast_rom && bank >= 0x80 { 6 } else { 8 };
+
+        match bank {
+            0x40..=0x7F => 8,
+            0xC0..=0xFF => rom_cycles,
+            _ => match offset {
+                0x0000..=0x1FFF => 8,
+                0x2000..=0x3FFF => 6,
+                0x4000..=0x41FF => 12,
+                0x4200..=0x5FFF => 6,
+                0x6000..=0x7FFF => 8,
+                _ => rom_cycles,
+            },
+        }
+    }
+
+    // Liga/desliga o log de DMA; ligar começa um log vazio
+    pub fn set_dma_log(&mut self, enabled: bool) {
+        self.dma_log = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    pub fn dma_log(&self) -> &[DmaTransfer] {
+        self.dma_log.as_deref().unwrap_or(&[])
+    }
+
+    // DMA de uso geral ($420B): executa cada canal habilitado, do 0 ao 7
+    fn run_dma(&mut self, channels: u8) {
+        if channels != 0 {
+            self.dma_stall += Self::DMA_OVERHEAD_CYCLES;
+        }
+
+        for channel in 0..8u16 {
+            if (channels & (1 << channel)) == 0 {
+                continue;
+            }
+
+            self.dma_stall += Self::DMA_CHANNEL_CYCLES;
+
+            let base = 0x4300 | (channel << 4);
+            let dmap = self.read_dma_registers(base);
+            let bbad = self.read_dma_registers(base | 0x01);
+            let mut a_addr = self.read_dma_registers(base | 0x02) as u16 |
+                             (self.read_dma_registers(base | 0x03) as u16) << 8;
+            let a_bank = self.read_dma_registers(base | 0x04);
+            let mut count = self.read_dma_registers(base | 0x05) as u16 |
+                            (self.read_dma_registers(base | 0x06) as u16) << 8;
+
+            if self.dma_log.is_some() {
+                let scanline = self.ppu.borrow().scanline;
+                let transfer = DmaTransfer {
+                    channel: channel as u8,
+                    source: ((a_bank as u32) << 16) | a_addr as u32,
+                    destination: 0x2100 | bbad as u16,
+                    length: if count == 0 { 0x10000 } else { count as u32 },
+                    scanline,
+                };
+                if let Some(log) = &mut self.dma_log {
+                    log.push(transfer);
+                }
+            }
+
+            // Sequência de registradores B-bus por modo de transferência
+            let pattern: &[u8] = match dmap & 0x07 {
+                0 => &[0],
+                1 => &[0, 1],
+                2 | 6 => &[0, 0],
+                3 | 7 => &[0, 0, 1, 1],
+                4 => &[0, 1, 2, 3],
+                _ => &[0, 1, 0, 1],
+            };
+
+            let b_to_a = (dmap & 0x80) != 0;
+            let a_step: i16 = if (dmap & 0x08) != 0 {
+                0
+            } else if (dmap & 0x10) != 0 {
+                -1
+            } else {
+                1
+            };
+
+            // DAS = 0 transfere 64KB
+            let mut index = 0;
+            loop {
+                let b_addr = 0x2100 | bbad.wrapping_add(pattern[index % pattern.len()]) as u32;
+                let a_full = ((a_bank as u32) << 16) | a_addr as u32;
+
+                if b_to_a {
+                    let value = self.read(b_addr);
+                    self.write(a_full, value);
+                } else {
+                    let value = self.read(a_full);
+                    self.write(b_addr, value);
+                }
+
+                // Cada byte custa 8 master cycles (um ciclo de CPU)
+                self.dma_stall += 1;
+                a_addr = a_addr.wrapping_add_signed(a_step);
+                index += 1;
+                count = count.wrapping_sub(1);
+
+                if count == 0 {
+                    break;
+                }
+            }
+
+            self.registers.insert(base | 0x02, a_addr as u8);
+            self.registers.insert(base | 0x03, (a_addr >> 8) as u8);
+            self.registers.insert(base | 0x05, 0);
+            self.registers.insert(base | 0x06, 0);
+        }
     }
 
     // Métodos auxiliares para VRAM, OAM, CGRAM
+    // Word da VRAM (32K words, little-endian) usado pelas buscas da PPU
+    pub fn vram_word(&self, addr: u16) -> u16 {
+        let index = (addr & 0x7FFF) as usize * 2;
+        (self.vram[index + 1] as u16) << 8 | self.vram[index] as u16
+    }
+
     pub fn read_vram(&self, addr: u16) -> u8 {
         if (addr as usize) < self.vram.len() {
             self.vram[addr as usize]
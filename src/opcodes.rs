@@ -1,6 +1,4 @@
-use std::collections::HashMap;
-
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 
 pub enum Operation {
     LoadA, LoadX, LoadY,
@@ -11,31 +9,35 @@ pub enum Operation {
 
     And, Or, Xor,
 
-    Xce, Rep, Sep, Tcd,
+    TestSetBits, TestResetBits,
+
+    Xce, Rep, Sep, Tcd, ExchangeBA,
 
     DecX, Rtl,
 
     Compare, CompareX, CompareY,
 
-    ShiftLeft, ShiftRight,
+    ShiftLeft, ShiftRight, RotateLeft, RotateRight,
 
     TransferAX, TransferAY, TransferXA, TransferXY, TransferYA, TransferYX, TransferSX, TransferXS,
     TransferSC, TransferCS,
 
     PushA, PullA, PushP, PullP, PushX, PullX, PushY, PullY,
 
-    JumpSubroutine, ReturnFromSubroutine, ReturnFromInterrupt, SoftwareInterrupt,
+    JumpSubroutine, ReturnFromSubroutine, ReturnFromInterrupt, SoftwareInterrupt, Coprocessor,
 
     SetFlag(u8), ClearFlag(u8),
 
     Jump, JumpIndirect,
 
+    BlockMoveNegative, BlockMovePositive,
+
     Branch { flag: u8, condition: bool },
 
-    Nop,
+    Nop, Wdm,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AddressingMode {
     Implied,
     Immediate,
@@ -48,11 +50,17 @@ pub enum AddressingMode {
     AbsoluteLong,
     AbsoluteLongIndexedX,
     Indirect,
+    AbsoluteIndexedIndirect,
     IndirectIndexed,
     IndexedIndirect,
+    DirectPageIndirect,
+    DirectPageIndirectLong,
+    DirectPageIndirectLongIndexedY,
+    StackRelative,
+    StackRelativeIndirectIndexed,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct OpcodeInfo {
     pub operation: Operation,
     pub mode: AddressingMode,
@@ -69,200 +77,383 @@ pub const FLAG_DECIMAL: u8 = 0x08;
 pub const FLAG_OVERFLOW: u8 = 0x40;
 pub const FLAG_NEGATIVE: u8 = 0x80;
 
-pub fn create_opcode_table() -> HashMap<u8, OpcodeInfo> {
-    let mut table = HashMap::new();
+// Tabela densa indexada pelo opcode: o dispatch é um acesso a array, sem hash
+pub fn create_opcode_table() -> [Option<OpcodeInfo>; 256] {
+    let mut table = [None; 256];
 
     //Flags
-    table.insert(0x18, OpcodeInfo { operation: ClearFlag(FLAG_CARRY), mode: Implied, cycles: 2 });
-    table.insert(0x38, OpcodeInfo { operation: SetFlag(FLAG_CARRY), mode: Implied, cycles: 2 });
-    table.insert(0x58, OpcodeInfo { operation: ClearFlag(FLAG_IRQ), mode: Implied, cycles: 2 });
-    table.insert(0x78, OpcodeInfo { operation: SetFlag(FLAG_IRQ), mode: Implied, cycles: 2 });
-    table.insert(0xB8, OpcodeInfo { operation: ClearFlag(FLAG_OVERFLOW), mode: Implied, cycles: 2 });
-    table.insert(0xD8, OpcodeInfo { operation: ClearFlag(FLAG_DECIMAL), mode: Implied, cycles: 2 });
-    table.insert(0xF8, OpcodeInfo { operation: SetFlag(FLAG_DECIMAL), mode: Implied, cycles: 2 });
+    table[0x18] = Some(OpcodeInfo { operation: ClearFlag(FLAG_CARRY), mode: Implied, cycles: 2 });
+    table[0x38] = Some(OpcodeInfo { operation: SetFlag(FLAG_CARRY), mode: Implied, cycles: 2 });
+    table[0x58] = Some(OpcodeInfo { operation: ClearFlag(FLAG_IRQ), mode: Implied, cycles: 2 });
+    table[0x78] = Some(OpcodeInfo { operation: SetFlag(FLAG_IRQ), mode: Implied, cycles: 2 });
+    table[0xB8] = Some(OpcodeInfo { operation: ClearFlag(FLAG_OVERFLOW), mode: Implied, cycles: 2 });
+    table[0xD8] = Some(OpcodeInfo { operation: ClearFlag(FLAG_DECIMAL), mode: Implied, cycles: 2 });
+    table[0xF8] = Some(OpcodeInfo { operation: SetFlag(FLAG_DECIMAL), mode: Implied, cycles: 2 });
 
     //Transfers
-    table.insert(0xAA, OpcodeInfo { operation: TransferAX, mode: Implied, cycles: 2 });
-    table.insert(0xA8, OpcodeInfo { operation: TransferAY, mode: Implied, cycles: 2 });
-    table.insert(0x8A, OpcodeInfo { operation: TransferXA, mode: Implied, cycles: 2 });
-    table.insert(0x98, OpcodeInfo { operation: TransferYA, mode: Implied, cycles: 2 });
-    table.insert(0x9B, OpcodeInfo { operation: TransferXY, mode: Implied, cycles: 2 });
-    table.insert(0xBB, OpcodeInfo { operation: TransferYX, mode: Implied, cycles: 2 });
-    table.insert(0xBA, OpcodeInfo { operation: TransferSX, mode: Implied, cycles: 2 });
-    table.insert(0x9A, OpcodeInfo { operation: TransferXS, mode: Implied, cycles: 2 });
-    table.insert(0x3B, OpcodeInfo { operation: TransferSC, mode: Implied, cycles: 2 });
-    table.insert(0x1B, OpcodeInfo { operation: TransferCS, mode: Implied, cycles: 2 });
+    table[0xAA] = Some(OpcodeInfo { operation: TransferAX, mode: Implied, cycles: 2 });
+    table[0xA8] = Some(OpcodeInfo { operation: TransferAY, mode: Implied, cycles: 2 });
+    table[0x8A] = Some(OpcodeInfo { operation: TransferXA, mode: Implied, cycles: 2 });
+    table[0x98] = Some(OpcodeInfo { operation: TransferYA, mode: Implied, cycles: 2 });
+    table[0x9B] = Some(OpcodeInfo { operation: TransferXY, mode: Implied, cycles: 2 });
+    table[0xBB] = Some(OpcodeInfo { operation: TransferYX, mode: Implied, cycles: 2 });
+    table[0xBA] = Some(OpcodeInfo { operation: TransferSX, mode: Implied, cycles: 2 });
+    table[0x9A] = Some(OpcodeInfo { operation: TransferXS, mode: Implied, cycles: 2 });
+    table[0x3B] = Some(OpcodeInfo { operation: TransferSC, mode: Implied, cycles: 2 });
+    table[0x1B] = Some(OpcodeInfo { operation: TransferCS, mode: Implied, cycles: 2 });
 
     //Load
-    table.insert(0xA9, OpcodeInfo { operation: LoadA, mode: Immediate, cycles: 2 });
-    table.insert(0xA5, OpcodeInfo { operation: LoadA, mode: DirectPage, cycles: 3 });
-    table.insert(0xB5, OpcodeInfo { operation: LoadA, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0xAD, OpcodeInfo { operation: LoadA, mode: Absolute, cycles: 4 });
-    table.insert(0xBD, OpcodeInfo { operation: LoadA, mode: AbsoluteIndexedX, cycles: 4 });
-    table.insert(0xB9, OpcodeInfo { operation: LoadA, mode: AbsoluteIndexedY, cycles: 4 });
-    table.insert(0xB1, OpcodeInfo { operation: LoadA, mode: IndirectIndexed, cycles: 5 });
-    table.insert(0xA1, OpcodeInfo { operation: LoadA, mode: IndexedIndirect, cycles: 6 });
-    table.insert(0xA2, OpcodeInfo { operation: LoadX, mode: Immediate, cycles: 2 });
-    table.insert(0xA6, OpcodeInfo { operation: LoadX, mode: DirectPage, cycles: 3 });
-    table.insert(0xB6, OpcodeInfo { operation: LoadX, mode: DirectPageIndexedY, cycles: 4 });
-    table.insert(0xAE, OpcodeInfo { operation: LoadX, mode: Absolute, cycles: 4 });
-    table.insert(0xBE, OpcodeInfo { operation: LoadX, mode: AbsoluteIndexedY, cycles: 4 });
-    table.insert(0xA0, OpcodeInfo { operation: LoadY, mode: Immediate, cycles: 2 });
-    table.insert(0xA4, OpcodeInfo { operation: LoadY, mode: DirectPage, cycles: 3 });
-    table.insert(0xB4, OpcodeInfo { operation: LoadY, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0xAC, OpcodeInfo { operation: LoadY, mode: Absolute, cycles: 4 });
-    table.insert(0xBC, OpcodeInfo { operation: LoadY, mode: AbsoluteIndexedX, cycles: 4 });
+    table[0xA9] = Some(OpcodeInfo { operation: LoadA, mode: Immediate, cycles: 2 });
+    table[0xA5] = Some(OpcodeInfo { operation: LoadA, mode: DirectPage, cycles: 3 });
+    table[0xB5] = Some(OpcodeInfo { operation: LoadA, mode: DirectPageIndexedX, cycles: 4 });
+    table[0xAD] = Some(OpcodeInfo { operation: LoadA, mode: Absolute, cycles: 4 });
+    table[0xBD] = Some(OpcodeInfo { operation: LoadA, mode: AbsoluteIndexedX, cycles: 4 });
+    table[0xB9] = Some(OpcodeInfo { operation: LoadA, mode: AbsoluteIndexedY, cycles: 4 });
+    table[0xB1] = Some(OpcodeInfo { operation: LoadA, mode: IndirectIndexed, cycles: 5 });
+    table[0xA1] = Some(OpcodeInfo { operation: LoadA, mode: IndexedIndirect, cycles: 6 });
+    table[0xA3] = Some(OpcodeInfo { operation: LoadA, mode: StackRelative, cycles: 4 });
+    table[0xB3] = Some(OpcodeInfo { operation: LoadA, mode: StackRelativeIndirectIndexed, cycles: 7 });
+    table[0xA2] = Some(OpcodeInfo { operation: LoadX, mode: Immediate, cycles: 2 });
+    table[0xA6] = Some(OpcodeInfo { operation: LoadX, mode: DirectPage, cycles: 3 });
+    table[0xB6] = Some(OpcodeInfo { operation: LoadX, mode: DirectPageIndexedY, cycles: 4 });
+    table[0xAE] = Some(OpcodeInfo { operation: LoadX, mode: Absolute, cycles: 4 });
+    table[0xBE] = Some(OpcodeInfo { operation: LoadX, mode: AbsoluteIndexedY, cycles: 4 });
+    table[0xA0] = Some(OpcodeInfo { operation: LoadY, mode: Immediate, cycles: 2 });
+    table[0xA4] = Some(OpcodeInfo { operation: LoadY, mode: DirectPage, cycles: 3 });
+    table[0xB4] = Some(OpcodeInfo { operation: LoadY, mode: DirectPageIndexedX, cycles: 4 });
+    table[0xAC] = Some(OpcodeInfo { operation: LoadY, mode: Absolute, cycles: 4 });
+    table[0xBC] = Some(OpcodeInfo { operation: LoadY, mode: AbsoluteIndexedX, cycles: 4 });
 
     //Store
-    table.insert(0x85, OpcodeInfo { operation: StoreA, mode: DirectPage, cycles: 3 });
-    table.insert(0x95, OpcodeInfo { operation: StoreA, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0x8D, OpcodeInfo { operation: StoreA, mode: Absolute, cycles: 4 });
-    table.insert(0x8F, OpcodeInfo { operation: StoreA, mode: AbsoluteLong, cycles: 5 });
-    table.insert(0x9D, OpcodeInfo { operation: StoreA, mode: AbsoluteIndexedX, cycles: 5 });
-    table.insert(0x9F, OpcodeInfo { operation: StoreA, mode: AbsoluteLongIndexedX, cycles: 5 });
-    table.insert(0x99, OpcodeInfo { operation: StoreA, mode: AbsoluteIndexedY, cycles: 5 });
-    table.insert(0x91, OpcodeInfo { operation: StoreA, mode: IndirectIndexed, cycles: 6 });
-    table.insert(0x81, OpcodeInfo { operation: StoreA, mode: IndexedIndirect, cycles: 6 });
-    table.insert(0x86, OpcodeInfo { operation: StoreX, mode: DirectPage, cycles: 3 });
-    table.insert(0x96, OpcodeInfo { operation: StoreX, mode: DirectPageIndexedY, cycles: 4 });
-    table.insert(0x8E, OpcodeInfo { operation: StoreX, mode: Absolute, cycles: 4 });
-    table.insert(0x84, OpcodeInfo { operation: StoreY, mode: DirectPage, cycles: 3 });
-    table.insert(0x94, OpcodeInfo { operation: StoreY, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0x8C, OpcodeInfo { operation: StoreY, mode: Absolute, cycles: 4 });
-    table.insert(0x64, OpcodeInfo { operation: StoreZero, mode: DirectPage, cycles: 3 });
-    table.insert(0x74, OpcodeInfo { operation: StoreZero, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0x9C, OpcodeInfo { operation: StoreZero, mode: Absolute, cycles: 4 });
-    table.insert(0x9E, OpcodeInfo { operation: StoreZero, mode: AbsoluteIndexedX, cycles: 5 });
-
-    table.insert(0xFB, OpcodeInfo { operation: Xce, mode: Implied, cycles: 2 });
-    table.insert(0xC2, OpcodeInfo { operation: Rep, mode: Immediate, cycles: 3 });
-    table.insert(0xE2, OpcodeInfo { operation: Sep, mode: Immediate, cycles: 3 });
-    table.insert(0x5B, OpcodeInfo { operation: Tcd, mode: Implied, cycles: 2 });
+    table[0x85] = Some(OpcodeInfo { operation: StoreA, mode: DirectPage, cycles: 3 });
+    table[0x95] = Some(OpcodeInfo { operation: StoreA, mode: DirectPageIndexedX, cycles: 4 });
+    table[0x8D] = Some(OpcodeInfo { operation: StoreA, mode: Absolute, cycles: 4 });
+    table[0x8F] = Some(OpcodeInfo { operation: StoreA, mode: AbsoluteLong, cycles: 5 });
+    table[0x9D] = Some(OpcodeInfo { operation: StoreA, mode: AbsoluteIndexedX, cycles: 5 });
+    table[0x9F] = Some(OpcodeInfo { operation: StoreA, mode: AbsoluteLongIndexedX, cycles: 5 });
+    table[0x99] = Some(OpcodeInfo { operation: StoreA, mode: AbsoluteIndexedY, cycles: 5 });
+    table[0x91] = Some(OpcodeInfo { operation: StoreA, mode: IndirectIndexed, cycles: 6 });
+    table[0x81] = Some(OpcodeInfo { operation: StoreA, mode: IndexedIndirect, cycles: 6 });
+    table[0x83] = Some(OpcodeInfo { operation: StoreA, mode: StackRelative, cycles: 4 });
+    table[0x93] = Some(OpcodeInfo { operation: StoreA, mode: StackRelativeIndirectIndexed, cycles: 7 });
+    table[0x86] = Some(OpcodeInfo { operation: StoreX, mode: DirectPage, cycles: 3 });
+    table[0x96] = Some(OpcodeInfo { operation: StoreX, mode: DirectPageIndexedY, cycles: 4 });
+    table[0x8E] = Some(OpcodeInfo { operation: StoreX, mode: Absolute, cycles: 4 });
+    table[0x84] = Some(OpcodeInfo { operation: StoreY, mode: DirectPage, cycles: 3 });
+    table[0x94] = Some(OpcodeInfo { operation: StoreY, mode: DirectPageIndexedX, cycles: 4 });
+    table[0x8C] = Some(OpcodeInfo { operation: StoreY, mode: Absolute, cycles: 4 });
+    table[0x64] = Some(OpcodeInfo { operation: StoreZero, mode: DirectPage, cycles: 3 });
+    table[0x74] = Some(OpcodeInfo { operation: StoreZero, mode: DirectPageIndexedX, cycles: 4 });
+    table[0x9C] = Some(OpcodeInfo { operation: StoreZero, mode: Absolute, cycles: 4 });
+    table[0x9E] = Some(OpcodeInfo { operation: StoreZero, mode: AbsoluteIndexedX, cycles: 5 });
+
+    table[0xFB] = Some(OpcodeInfo { operation: Xce, mode: Implied, cycles: 2 });
+    table[0xEB] = Some(OpcodeInfo { operation: ExchangeBA, mode: Implied, cycles: 3 });
+    table[0xC2] = Some(OpcodeInfo { operation: Rep, mode: Immediate, cycles: 3 });
+    table[0xE2] = Some(OpcodeInfo { operation: Sep, mode: Immediate, cycles: 3 });
+    table[0x5B] = Some(OpcodeInfo { operation: Tcd, mode: Implied, cycles: 2 });
 
     //Arithmetic
-    table.insert(0x69, OpcodeInfo { operation: Add, mode: Immediate, cycles: 2 });
-    table.insert(0x65, OpcodeInfo { operation: Add, mode: DirectPage, cycles: 3 });
-    table.insert(0x75, OpcodeInfo { operation: Add, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0x6D, OpcodeInfo { operation: Add, mode: Absolute, cycles: 4 });
-    table.insert(0x7D, OpcodeInfo { operation: Add, mode: AbsoluteIndexedX, cycles: 4 });
-    table.insert(0x79, OpcodeInfo { operation: Add, mode: AbsoluteIndexedY, cycles: 4 });
-    table.insert(0x71, OpcodeInfo { operation: Add, mode: IndirectIndexed, cycles: 5 });
-    table.insert(0x61, OpcodeInfo { operation: Add, mode: IndexedIndirect, cycles: 6 });
-
-    table.insert(0xE9, OpcodeInfo { operation: Sub, mode: Immediate, cycles: 2 });
-    table.insert(0xE5, OpcodeInfo { operation: Sub, mode: DirectPage, cycles: 3 });
-    table.insert(0xF5, OpcodeInfo { operation: Sub, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0xED, OpcodeInfo { operation: Sub, mode: Absolute, cycles: 4 });
-    table.insert(0xFD, OpcodeInfo { operation: Sub, mode: AbsoluteIndexedX, cycles: 4 });
-    table.insert(0xF9, OpcodeInfo { operation: Sub, mode: AbsoluteIndexedY, cycles: 4 });
-    table.insert(0xF1, OpcodeInfo { operation: Sub, mode: IndirectIndexed, cycles: 5 });
-    table.insert(0xE1, OpcodeInfo { operation: Sub, mode: IndexedIndirect, cycles: 6 });
-
-    table.insert(0x1A, OpcodeInfo { operation: Inc, mode: Implied, cycles: 2 });
-    table.insert(0xE6, OpcodeInfo { operation: Inc, mode: DirectPage, cycles: 5 });
-    table.insert(0xEE, OpcodeInfo { operation: Inc, mode: Absolute, cycles: 6 });
-
-    table.insert(0x3A, OpcodeInfo { operation: Dec, mode: Implied, cycles: 2 });
-    table.insert(0xC6, OpcodeInfo { operation: Dec, mode: DirectPage, cycles: 5 });
-    table.insert(0xCE, OpcodeInfo { operation: Dec, mode: Absolute, cycles: 6 });
-
-    table.insert(0x29, OpcodeInfo { operation: And, mode: Immediate, cycles: 2 });
-    table.insert(0x25, OpcodeInfo { operation: And, mode: DirectPage, cycles: 3 });
-    table.insert(0x35, OpcodeInfo { operation: And, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0x2D, OpcodeInfo { operation: And, mode: Absolute, cycles: 4 });
-    table.insert(0x3D, OpcodeInfo { operation: And, mode: AbsoluteIndexedX, cycles: 4 });
-    table.insert(0x39, OpcodeInfo { operation: And, mode: AbsoluteIndexedY, cycles: 4 });
-    table.insert(0x31, OpcodeInfo { operation: And, mode: IndirectIndexed, cycles: 5 });
-    table.insert(0x21, OpcodeInfo { operation: And, mode: IndexedIndirect, cycles: 6 });
-
-    table.insert(0x09, OpcodeInfo { operation: Or, mode: Immediate, cycles: 2 });
-    table.insert(0x05, OpcodeInfo { operation: Or, mode: DirectPage, cycles: 3 });
-    table.insert(0x15, OpcodeInfo { operation: Or, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0x0D, OpcodeInfo { operation: Or, mode: Absolute, cycles: 4 });
-    table.insert(0x1D, OpcodeInfo { operation: Or, mode: AbsoluteIndexedX, cycles: 4 });
-    table.insert(0x19, OpcodeInfo { operation: Or, mode: AbsoluteIndexedY, cycles: 4 });
-    table.insert(0x11, OpcodeInfo { operation: Or, mode: IndirectIndexed, cycles: 5 });
-    table.insert(0x01, OpcodeInfo { operation: Or, mode: IndexedIndirect, cycles: 6 });
-
-    table.insert(0x49, OpcodeInfo { operation: Xor, mode: Immediate, cycles: 2 });
-    table.insert(0x45, OpcodeInfo { operation: Xor, mode: DirectPage, cycles: 3 });
-    table.insert(0x55, OpcodeInfo { operation: Xor, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0x4D, OpcodeInfo { operation: Xor, mode: Absolute, cycles: 4 });
-    table.insert(0x5D, OpcodeInfo { operation: Xor, mode: AbsoluteIndexedX, cycles: 4 });
-    table.insert(0x59, OpcodeInfo { operation: Xor, mode: AbsoluteIndexedY, cycles: 4 });
-    table.insert(0x51, OpcodeInfo { operation: Xor, mode: IndirectIndexed, cycles: 5 });
-    table.insert(0x41, OpcodeInfo { operation: Xor, mode: IndexedIndirect, cycles: 6 });
-
-    table.insert(0xC9, OpcodeInfo { operation: Compare, mode: Immediate, cycles: 2 });
-    table.insert(0xC5, OpcodeInfo { operation: Compare, mode: DirectPage, cycles: 3 });
-    table.insert(0xD5, OpcodeInfo { operation: Compare, mode: DirectPageIndexedX, cycles: 4 });
-    table.insert(0xCD, OpcodeInfo { operation: Compare, mode: Absolute, cycles: 4 });
-    table.insert(0xDD, OpcodeInfo { operation: Compare, mode: AbsoluteIndexedX, cycles: 4 });
-    table.insert(0xD9, OpcodeInfo { operation: Compare, mode: AbsoluteIndexedY, cycles: 4 });
-    table.insert(0xD1, OpcodeInfo { operation: Compare, mode: IndirectIndexed, cycles: 5 });
-    table.insert(0xC1, OpcodeInfo { operation: Compare, mode: IndexedIndirect, cycles: 6 });
-
-    table.insert(0xE0, OpcodeInfo {operation: CompareX, mode: Immediate, cycles: 2});
-    table.insert(0xE4, OpcodeInfo {operation: CompareX, mode: DirectPage, cycles: 3});
-    table.insert(0xEC, OpcodeInfo {operation: CompareX, mode: Absolute, cycles: 4});
-
-    table.insert(0xC0, OpcodeInfo {operation: CompareY, mode: Immediate, cycles: 2});
-    table.insert(0xC4, OpcodeInfo {operation: CompareY, mode: DirectPage, cycles: 3});
-    table.insert(0xCC, OpcodeInfo {operation: CompareY, mode: Absolute, cycles: 4});
-
-    table.insert(0xCA, OpcodeInfo { operation: DecX, mode: Implied, cycles: 2 });
-    table.insert(0x6B, OpcodeInfo { operation: Rtl, mode: Implied, cycles: 6 });
+    table[0x69] = Some(OpcodeInfo { operation: Add, mode: Immediate, cycles: 2 });
+    table[0x65] = Some(OpcodeInfo { operation: Add, mode: DirectPage, cycles: 3 });
+    table[0x75] = Some(OpcodeInfo { operation: Add, mode: DirectPageIndexedX, cycles: 4 });
+    table[0x6D] = Some(OpcodeInfo { operation: Add, mode: Absolute, cycles: 4 });
+    table[0x7D] = Some(OpcodeInfo { operation: Add, mode: AbsoluteIndexedX, cycles: 4 });
+    table[0x79] = Some(OpcodeInfo { operation: Add, mode: AbsoluteIndexedY, cycles: 4 });
+    table[0x71] = Some(OpcodeInfo { operation: Add, mode: IndirectIndexed, cycles: 5 });
+    table[0x61] = Some(OpcodeInfo { operation: Add, mode: IndexedIndirect, cycles: 6 });
+    table[0x63] = Some(OpcodeInfo { operation: Add, mode: StackRelative, cycles: 4 });
+    table[0x73] = Some(OpcodeInfo { operation: Add, mode: StackRelativeIndirectIndexed, cycles: 7 });
+
+    table[0xE9] = Some(OpcodeInfo { operation: Sub, mode: Immediate, cycles: 2 });
+    table[0xE5] = Some(OpcodeInfo { operation: Sub, mode: DirectPage, cycles: 3 });
+    table[0xF5] = Some(OpcodeInfo { operation: Sub, mode: DirectPageIndexedX, cycles: 4 });
+    table[0xED] = Some(OpcodeInfo { operation: Sub, mode: Absolute, cycles: 4 });
+    table[0xFD] = Some(OpcodeInfo { operation: Sub, mode: AbsoluteIndexedX, cycles: 4 });
+    table[0xF9] = Some(OpcodeInfo { operation: Sub, mode: AbsoluteIndexedY, cycles: 4 });
+    table[0xF1] = Some(OpcodeInfo { operation: Sub, mode: IndirectIndexed, cycles: 5 });
+    table[0xE1] = Some(OpcodeInfo { operation: Sub, mode: IndexedIndirect, cycles: 6 });
+    table[0xE3] = Some(OpcodeInfo { operation: Sub, mode: StackRelative, cycles: 4 });
+    table[0xF3] = Some(OpcodeInfo { operation: Sub, mode: StackRelativeIndirectIndexed, cycles: 7 });
+
+    table[0x1A] = Some(OpcodeInfo { operation: Inc, mode: Implied, cycles: 2 });
+    table[0xE6] = Some(OpcodeInfo { operation: Inc, mode: DirectPage, cycles: 5 });
+    table[0xEE] = Some(OpcodeInfo { operation: Inc, mode: Absolute, cycles: 6 });
+
+    table[0x3A] = Some(OpcodeInfo { operation: Dec, mode: Implied, cycles: 2 });
+    table[0xC6] = Some(OpcodeInfo { operation: Dec, mode: DirectPage, cycles: 5 });
+    table[0xCE] = Some(OpcodeInfo { operation: Dec, mode: Absolute, cycles: 6 });
+
+    table[0x29] = Some(OpcodeInfo { operation: And, mode: Immediate, cycles: 2 });
+    table[0x25] = Some(OpcodeInfo { operation: And, mode: DirectPage, cycles: 3 });
+    table[0x35] = Some(OpcodeInfo { operation: And, mode: DirectPageIndexedX, cycles: 4 });
+    table[0x2D] = Some(OpcodeInfo { operation: And, mode: Absolute, cycles: 4 });
+    table[0x3D] = Some(OpcodeInfo { operation: And, mode: AbsoluteIndexedX, cycles: 4 });
+    table[0x39] = Some(OpcodeInfo { operation: And, mode: AbsoluteIndexedY, cycles: 4 });
+    table[0x31] = Some(OpcodeInfo { operation: And, mode: IndirectIndexed, cycles: 5 });
+    table[0x21] = Some(OpcodeInfo { operation: And, mode: IndexedIndirect, cycles: 6 });
+    table[0x32] = Some(OpcodeInfo { operation: And, mode: DirectPageIndirect, cycles: 5 });
+    table[0x27] = Some(OpcodeInfo { operation: And, mode: DirectPageIndirectLong, cycles: 6 });
+    table[0x37] = Some(OpcodeInfo { operation: And, mode: DirectPageIndirectLongIndexedY, cycles: 6 });
+    table[0x2F] = Some(OpcodeInfo { operation: And, mode: AbsoluteLong, cycles: 5 });
+    table[0x3F] = Some(OpcodeInfo { operation: And, mode: AbsoluteLongIndexedX, cycles: 5 });
+    table[0x23] = Some(OpcodeInfo { operation: And, mode: StackRelative, cycles: 4 });
+    table[0x33] = Some(OpcodeInfo { operation: And, mode: StackRelativeIndirectIndexed, cycles: 7 });
+
+    table[0x09] = Some(OpcodeInfo { operation: Or, mode: Immediate, cycles: 2 });
+    table[0x05] = Some(OpcodeInfo { operation: Or, mode: DirectPage, cycles: 3 });
+    table[0x15] = Some(OpcodeInfo { operation: Or, mode: DirectPageIndexedX, cycles: 4 });
+    table[0x0D] = Some(OpcodeInfo { operation: Or, mode: Absolute, cycles: 4 });
+    table[0x1D] = Some(OpcodeInfo { operation: Or, mode: AbsoluteIndexedX, cycles: 4 });
+    table[0x19] = Some(OpcodeInfo { operation: Or, mode: AbsoluteIndexedY, cycles: 4 });
+    table[0x11] = Some(OpcodeInfo { operation: Or, mode: IndirectIndexed, cycles: 5 });
+    table[0x01] = Some(OpcodeInfo { operation: Or, mode: IndexedIndirect, cycles: 6 });
+    table[0x12] = Some(OpcodeInfo { operation: Or, mode: DirectPageIndirect, cycles: 5 });
+    table[0x07] = Some(OpcodeInfo { operation: Or, mode: DirectPageIndirectLong, cycles: 6 });
+    table[0x17] = Some(OpcodeInfo { operation: Or, mode: DirectPageIndirectLongIndexedY, cycles: 6 });
+    table[0x0F] = Some(OpcodeInfo { operation: Or, mode: AbsoluteLong, cycles: 5 });
+    table[0x1F] = Some(OpcodeInfo { operation: Or, mode: AbsoluteLongIndexedX, cycles: 5 });
+    table[0x03] = Some(OpcodeInfo { operation: Or, mode: StackRelative, cycles: 4 });
+    table[0x13] = Some(OpcodeInfo { operation: Or, mode: StackRelativeIndirectIndexed, cycles: 7 });
+
+    table[0x04] = Some(OpcodeInfo { operation: TestSetBits, mode: DirectPage, cycles: 5 });
+    table[0x0C] = Some(OpcodeInfo { operation: TestSetBits, mode: Absolute, cycles: 6 });
+    table[0x14] = Some(OpcodeInfo { operation: TestResetBits, mode: DirectPage, cycles: 5 });
+    table[0x1C] = Some(OpcodeInfo { operation: TestResetBits, mode: Absolute, cycles: 6 });
+
+    table[0x49] = Some(OpcodeInfo { operation: Xor, mode: Immediate, cycles: 2 });
+    table[0x45] = Some(OpcodeInfo { operation: Xor, mode: DirectPage, cycles: 3 });
+    table[0x55] = Some(OpcodeInfo { operation: Xor, mode: DirectPageIndexedX, cycles: 4 });
+    table[0x4D] = Some(OpcodeInfo { operation: Xor, mode: Absolute, cycles: 4 });
+    table[0x5D] = Some(OpcodeInfo { operation: Xor, mode: AbsoluteIndexedX, cycles: 4 });
+    table[0x59] = Some(OpcodeInfo { operation: Xor, mode: AbsoluteIndexedY, cycles: 4 });
+    table[0x51] = Some(OpcodeInfo { operation: Xor, mode: IndirectIndexed, cycles: 5 });
+    table[0x41] = Some(OpcodeInfo { operation: Xor, mode: IndexedIndirect, cycles: 6 });
+    table[0x52] = Some(OpcodeInfo { operation: Xor, mode: DirectPageIndirect, cycles: 5 });
+    table[0x47] = Some(OpcodeInfo { operation: Xor, mode: DirectPageIndirectLong, cycles: 6 });
+    table[0x57] = Some(OpcodeInfo { operation: Xor, mode: DirectPageIndirectLongIndexedY, cycles: 6 });
+    table[0x4F] = Some(OpcodeInfo { operation: Xor, mode: AbsoluteLong, cycles: 5 });
+    table[0x5F] = Some(OpcodeInfo { operation: Xor, mode: AbsoluteLongIndexedX, cycles: 5 });
+    table[0x43] = Some(OpcodeInfo { operation: Xor, mode: StackRelative, cycles: 4 });
+    table[0x53] = Some(OpcodeInfo { operation: Xor, mode: StackRelativeIndirectIndexed, cycles: 7 });
+
+    table[0xC9] = Some(OpcodeInfo { operation: Compare, mode: Immediate, cycles: 2 });
+    table[0xC5] = Some(OpcodeInfo { operation: Compare, mode: DirectPage, cycles: 3 });
+    table[0xD5] = Some(OpcodeInfo { operation: Compare, mode: DirectPageIndexedX, cycles: 4 });
+    table[0xCD] = Some(OpcodeInfo { operation: Compare, mode: Absolute, cycles: 4 });
+    table[0xDD] = Some(OpcodeInfo { operation: Compare, mode: AbsoluteIndexedX, cycles: 4 });
+    table[0xD9] = Some(OpcodeInfo { operation: Compare, mode: AbsoluteIndexedY, cycles: 4 });
+    table[0xD1] = Some(OpcodeInfo { operation: Compare, mode: IndirectIndexed, cycles: 5 });
+    table[0xC1] = Some(OpcodeInfo { operation: Compare, mode: IndexedIndirect, cycles: 6 });
+    table[0xC3] = Some(OpcodeInfo { operation: Compare, mode: StackRelative, cycles: 4 });
+    table[0xD3] = Some(OpcodeInfo { operation: Compare, mode: StackRelativeIndirectIndexed, cycles: 7 });
+
+    table[0xE0] = Some(OpcodeInfo {operation: CompareX, mode: Immediate, cycles: 2});
+    table[0xE4] = Some(OpcodeInfo {operation: CompareX, mode: DirectPage, cycles: 3});
+    table[0xEC] = Some(OpcodeInfo {operation: CompareX, mode: Absolute, cycles: 4});
+
+    table[0xC0] = Some(OpcodeInfo {operation: CompareY, mode: Immediate, cycles: 2});
+    table[0xC4] = Some(OpcodeInfo {operation: CompareY, mode: DirectPage, cycles: 3});
+    table[0xCC] = Some(OpcodeInfo {operation: CompareY, mode: Absolute, cycles: 4});
+
+    table[0xCA] = Some(OpcodeInfo { operation: DecX, mode: Implied, cycles: 2 });
+    table[0x6B] = Some(OpcodeInfo { operation: Rtl, mode: Implied, cycles: 6 });
 
     //Stacks
-    table.insert(0x48, OpcodeInfo { operation: PushA, mode: Implied, cycles: 3 });
-    table.insert(0x68, OpcodeInfo { operation: PullA, mode: Implied, cycles: 4 });
-    table.insert(0x08, OpcodeInfo { operation: PushP, mode: Implied, cycles: 3 });
-    table.insert(0x28, OpcodeInfo { operation: PullP, mode: Implied, cycles: 4 });
-    table.insert(0xDA, OpcodeInfo { operation: PushX, mode: Implied, cycles: 3 });
-    table.insert(0xFA, OpcodeInfo { operation: PullX, mode: Implied, cycles: 4 });
-    table.insert(0x5A, OpcodeInfo { operation: PushY, mode: Implied, cycles: 3 });
-    table.insert(0x7A, OpcodeInfo { operation: PullY, mode: Implied, cycles: 4 }); 
+    table[0x48] = Some(OpcodeInfo { operation: PushA, mode: Implied, cycles: 3 });
+    table[0x68] = Some(OpcodeInfo { operation: PullA, mode: Implied, cycles: 4 });
+    table[0x08] = Some(OpcodeInfo { operation: PushP, mode: Implied, cycles: 3 });
+    table[0x28] = Some(OpcodeInfo { operation: PullP, mode: Implied, cycles: 4 });
+    table[0xDA] = Some(OpcodeInfo { operation: PushX, mode: Implied, cycles: 3 });
+    table[0xFA] = Some(OpcodeInfo { operation: PullX, mode: Implied, cycles: 4 });
+    table[0x5A] = Some(OpcodeInfo { operation: PushY, mode: Implied, cycles: 3 });
+    table[0x7A] = Some(OpcodeInfo { operation: PullY, mode: Implied, cycles: 4 }); 
 
     //Shifts
-    table.insert(0x0A, OpcodeInfo { operation: ShiftLeft, mode: Implied, cycles: 2 });
-    table.insert(0x06, OpcodeInfo { operation: ShiftLeft, mode: DirectPage, cycles: 5 });
-    table.insert(0x0E, OpcodeInfo { operation: ShiftLeft, mode: Absolute, cycles: 6 });
-
-    table.insert(0x4A, OpcodeInfo { operation: ShiftRight, mode: Implied, cycles: 2 });
-    table.insert(0x46, OpcodeInfo { operation: ShiftRight, mode: DirectPage, cycles: 5 });
-    table.insert(0x4E, OpcodeInfo { operation: ShiftRight, mode: Absolute, cycles: 6 });
+    table[0x0A] = Some(OpcodeInfo { operation: ShiftLeft, mode: Implied, cycles: 2 });
+    table[0x06] = Some(OpcodeInfo { operation: ShiftLeft, mode: DirectPage, cycles: 5 });
+    table[0x0E] = Some(OpcodeInfo { operation: ShiftLeft, mode: Absolute, cycles: 6 });
+
+    table[0x4A] = Some(OpcodeInfo { operation: ShiftRight, mode: Implied, cycles: 2 });
+    table[0x46] = Some(OpcodeInfo { operation: ShiftRight, mode: DirectPage, cycles: 5 });
+    table[0x4E] = Some(OpcodeInfo { operation: ShiftRight, mode: Absolute, cycles: 6 });
+
+    table[0x2A] = Some(OpcodeInfo { operation: RotateLeft, mode: Implied, cycles: 2 });
+    table[0x26] = Some(OpcodeInfo { operation: RotateLeft, mode: DirectPage, cycles: 5 });
+    table[0x2E] = Some(OpcodeInfo { operation: RotateLeft, mode: Absolute, cycles: 6 });
+    table[0x36] = Some(OpcodeInfo { operation: RotateLeft, mode: DirectPageIndexedX, cycles: 6 });
+    table[0x3E] = Some(OpcodeInfo { operation: RotateLeft, mode: AbsoluteIndexedX, cycles: 7 });
+
+    table[0x6A] = Some(OpcodeInfo { operation: RotateRight, mode: Implied, cycles: 2 });
+    table[0x66] = Some(OpcodeInfo { operation: RotateRight, mode: DirectPage, cycles: 5 });
+    table[0x6E] = Some(OpcodeInfo { operation: RotateRight, mode: Absolute, cycles: 6 });
+    table[0x76] = Some(OpcodeInfo { operation: RotateRight, mode: DirectPageIndexedX, cycles: 6 });
+    table[0x7E] = Some(OpcodeInfo { operation: RotateRight, mode: AbsoluteIndexedX, cycles: 7 });
 
     //Subroutines
-    table.insert(0x20, OpcodeInfo { operation: JumpSubroutine, mode: Absolute, cycles: 6 });
-    table.insert(0x60, OpcodeInfo { operation: ReturnFromSubroutine, mode: Implied, cycles: 6 });
-    table.insert(0x40, OpcodeInfo { operation: ReturnFromInterrupt, mode: Implied, cycles: 6 });
-    table.insert(0x00, OpcodeInfo { operation: SoftwareInterrupt, mode: Implied, cycles: 7 });
+    table[0x20] = Some(OpcodeInfo { operation: JumpSubroutine, mode: Absolute, cycles: 6 });
+    table[0xFC] = Some(OpcodeInfo { operation: JumpSubroutine, mode: AbsoluteIndexedIndirect, cycles: 8 });
+    table[0x60] = Some(OpcodeInfo { operation: ReturnFromSubroutine, mode: Implied, cycles: 6 });
+    table[0x40] = Some(OpcodeInfo { operation: ReturnFromInterrupt, mode: Implied, cycles: 6 });
+    table[0x00] = Some(OpcodeInfo { operation: SoftwareInterrupt, mode: Implied, cycles: 7 });
+    table[0x02] = Some(OpcodeInfo { operation: Coprocessor, mode: Implied, cycles: 7 });
 
     //Jumps
-    table.insert(0x4C, OpcodeInfo { operation: Jump, mode: Absolute, cycles: 3 });
-    table.insert(0x6C, OpcodeInfo { operation: JumpIndirect, mode: Indirect, cycles: 5 });
+    table[0x4C] = Some(OpcodeInfo { operation: Jump, mode: Absolute, cycles: 3 });
+    table[0x6C] = Some(OpcodeInfo { operation: JumpIndirect, mode: Indirect, cycles: 5 });
+    table[0x7C] = Some(OpcodeInfo { operation: Jump, mode: AbsoluteIndexedIndirect, cycles: 6 });
 
     //Branches
-    table.insert(0x10, OpcodeInfo { operation: Branch { flag: FLAG_NEGATIVE, condition: false }, mode: Implied, cycles: 2 });
-    table.insert(0x30, OpcodeInfo { operation: Branch { flag: FLAG_NEGATIVE, condition: true }, mode: Implied, cycles: 2 });
-    table.insert(0x50, OpcodeInfo { operation: Branch { flag: FLAG_OVERFLOW, condition: false }, mode: Implied, cycles: 2 });
-    table.insert(0x70, OpcodeInfo { operation: Branch { flag: FLAG_OVERFLOW, condition: true }, mode: Implied, cycles: 2 });
-    table.insert(0x90, OpcodeInfo { operation: Branch { flag: FLAG_CARRY, condition: false }, mode: Implied, cycles: 2 });
-    table.insert(0xB0, OpcodeInfo { operation: Branch { flag: FLAG_CARRY, condition: true }, mode: Implied, cycles: 2 });
-    table.insert(0xD0, OpcodeInfo { operation: Branch { flag: FLAG_ZERO, condition: false }, mode: Implied, cycles: 2 });
-    table.insert(0xF0, OpcodeInfo { operation: Branch { flag: FLAG_ZERO, condition: true }, mode: Implied, cycles: 2 });
+    table[0x10] = Some(OpcodeInfo { operation: Branch { flag: FLAG_NEGATIVE, condition: false }, mode: Implied, cycles: 2 });
+    table[0x30] = Some(OpcodeInfo { operation: Branch { flag: FLAG_NEGATIVE, condition: true }, mode: Implied, cycles: 2 });
+    table[0x50] = Some(OpcodeInfo { operation: Branch { flag: FLAG_OVERFLOW, condition: false }, mode: Implied, cycles: 2 });
+    table[0x70] = Some(OpcodeInfo { operation: Branch { flag: FLAG_OVERFLOW, condition: true }, mode: Implied, cycles: 2 });
+    table[0x90] = Some(OpcodeInfo { operation: Branch { flag: FLAG_CARRY, condition: false }, mode: Implied, cycles: 2 });
+    table[0xB0] = Some(OpcodeInfo { operation: Branch { flag: FLAG_CARRY, condition: true }, mode: Implied, cycles: 2 });
+    table[0xD0] = Some(OpcodeInfo { operation: Branch { flag: FLAG_ZERO, condition: false }, mode: Implied, cycles: 2 });
+    table[0xF0] = Some(OpcodeInfo { operation: Branch { flag: FLAG_ZERO, condition: true }, mode: Implied, cycles: 2 });
+
+    //Block moves (ciclos por byte)
+    table[0x54] = Some(OpcodeInfo { operation: BlockMoveNegative, mode: Implied, cycles: 7 });
+    table[0x44] = Some(OpcodeInfo { operation: BlockMovePositive, mode: Implied, cycles: 7 });
 
     //Placeholder
-    table.insert(0xEA, OpcodeInfo { operation: Nop, mode: Implied, cycles: 2 });
+    table[0xEA] = Some(OpcodeInfo { operation: Nop, mode: Implied, cycles: 2 });
+    table[0x42] = Some(OpcodeInfo { operation: Wdm, mode: Implied, cycles: 2 });
 
     table
 }
 
 use std::sync::OnceLock;
 
-static OPCODE_MAP: OnceLock<HashMap<u8, OpcodeInfo>> = OnceLock::new();
+static OPCODE_TABLE: OnceLock<[Option<OpcodeInfo>; 256]> = OnceLock::new();
 
 pub fn get_opcode_info(opcode: u8) -> Option<&'static OpcodeInfo> {
-    let map = OPCODE_MAP.get_or_init(|| create_opcode_table());
-    map.get(&opcode)
-}
\ No newline at end of file
+    let table = OPCODE_TABLE.get_or_init(create_opcode_table);
+    table[opcode as usize].as_ref()
+}
+
+// Opcodes decodificados pela tabela, em ordem crescente
+pub fn implemented_opcodes() -> Vec<u8> {
+    (0..=255u8).filter(|&opcode| get_opcode_info(opcode).is_some()).collect()
+}
+
+// Fração do espaço de 256 opcodes já decodificada
+pub fn coverage() -> f32 {
+    implemented_opcodes().len() as f32 / 256.0
+}
+
+// Mnemônico de três letras do opcode, para rótulos em frontends/debuggers
+pub fn mnemonic(opcode: u8) -> Option<&'static str> {
+    get_opcode_info(opcode).map(|info| operation_mnemonic(info.operation))
+}
+
+// Tamanho da instrução em bytes, incluindo o opcode. m_flag/x_flag ligados = 8 bits;
+// opcodes fora da tabela contam como 1 byte
+pub fn instruction_length(opcode: u8, m_flag: bool, x_flag: bool) -> u8 {
+    let Some(info) = get_opcode_info(opcode) else {
+        return 1;
+    };
+
+    let operand = match info.mode {
+        Implied => match info.operation {
+            Branch { .. } | SoftwareInterrupt | Coprocessor | Wdm => 1,
+            BlockMoveNegative | BlockMovePositive => 2,
+            _ => 0,
+        },
+        Immediate => match info.operation {
+            Rep | Sep => 1,
+            LoadX | LoadY | CompareX | CompareY => if x_flag { 1 } else { 2 },
+            _ => if m_flag { 1 } else { 2 },
+        },
+        DirectPage | DirectPageIndexedX | DirectPageIndexedY | IndirectIndexed | IndexedIndirect |
+        DirectPageIndirect | DirectPageIndirectLong | DirectPageIndirectLongIndexedY |
+        StackRelative | StackRelativeIndirectIndexed => 1,
+        Absolute | AbsoluteIndexedX | AbsoluteIndexedY | Indirect | AbsoluteIndexedIndirect => 2,
+        AbsoluteLong | AbsoluteLongIndexedX => 3,
+    };
+
+    1 + operand
+}
+
+// Combinações de flag sem instrução própria caem em "???"
+fn operation_mnemonic(operation: Operation) -> &'static str {
+    match operation {
+        LoadA => "LDA",
+        LoadX => "LDX",
+        LoadY => "LDY",
+        StoreA => "STA",
+        StoreX => "STX",
+        StoreY => "STY",
+        StoreZero => "STZ",
+        Add => "ADC",
+        Sub => "SBC",
+        Inc => "INC",
+        Dec => "DEC",
+        And => "AND",
+        Or => "ORA",
+        Xor => "EOR",
+        TestSetBits => "TSB",
+        TestResetBits => "TRB",
+        Xce => "XCE",
+        Rep => "REP",
+        Sep => "SEP",
+        Tcd => "TCD",
+        ExchangeBA => "XBA",
+        DecX => "DEX",
+        Rtl => "RTL",
+        Compare => "CMP",
+        CompareX => "CPX",
+        CompareY => "CPY",
+        ShiftLeft => "ASL",
+        ShiftRight => "LSR",
+        RotateLeft => "ROL",
+        RotateRight => "ROR",
+        TransferAX => "TAX",
+        TransferAY => "TAY",
+        TransferXA => "TXA",
+        TransferXY => "TXY",
+        TransferYA => "TYA",
+        TransferYX => "TYX",
+        TransferSX => "TSX",
+        TransferXS => "TXS",
+        TransferSC => "TSC",
+        TransferCS => "TCS",
+        PushA => "PHA",
+        PullA => "PLA",
+        PushP => "PHP",
+        PullP => "PLP",
+        PushX => "PHX",
+        PullX => "PLX",
+        PushY => "PHY",
+        PullY => "PLY",
+        JumpSubroutine => "JSR",
+        ReturnFromSubroutine => "RTS",
+        ReturnFromInterrupt => "RTI",
+        SoftwareInterrupt => "BRK",
+        Coprocessor => "COP",
+        SetFlag(FLAG_CARRY) => "SEC",
+        SetFlag(FLAG_IRQ) => "SEI",
+        SetFlag(FLAG_DECIMAL) => "SED",
+        SetFlag(_) => "???",
+        ClearFlag(FLAG_CARRY) => "CLC",
+        ClearFlag(FLAG_IRQ) => "CLI",
+        ClearFlag(FLAG_DECIMAL) => "CLD",
+        ClearFlag(FLAG_OVERFLOW) => "CLV",
+        ClearFlag(_) => "???",
+        Jump | JumpIndirect => "JMP",
+        BlockMoveNegative => "MVN",
+        BlockMovePositive => "MVP",
+        Branch { flag: FLAG_NEGATIVE, condition: false } => "BPL",
+        Branch { flag: FLAG_NEGATIVE, condition: true } => "BMI",
+        Branch { flag: FLAG_OVERFLOW, condition: false } => "BVC",
+        Branch { flag: FLAG_OVERFLOW, condition: true } => "BVS",
+        Branch { flag: FLAG_CARRY, condition: false } => "BCC",
+        Branch { flag: FLAG_CARRY, condition: true } => "BCS",
+        Branch { flag: FLAG_ZERO, condition: false } => "BNE",
+        Branch { flag: FLAG_ZERO, condition: true } => "BEQ",
+        Branch { .. } => "???",
+        Nop => "NOP",
+        Wdm => "WDM",
+    }
+}
@@ -1,4 +1,14 @@
-use crate::memory::Memory;
+use crate::memory::{Memory, Region};
+use crate::state::{self, Reader};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+
+// Buffer de destino com tamanho diferente do framebuffer atual (largura x 224)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderError {
+    BufferSize { expected: usize, actual: usize },
+}
 
 #[derive(Debug, Clone, Copy)]
 pub enum VideoMode {
@@ -12,6 +22,76 @@ pub enum VideoMode {
     Mode7,
 }
 
+impl VideoMode {
+    // Bits 0-2 de BGMODE ($2105)
+    fn from_bits(value: u8) -> Self {
+        match value & 0x07 {
+            0 => VideoMode::Mode0,
+            1 => VideoMode::Mode1,
+            2 => VideoMode::Mode2,
+            3 => VideoMode::Mode3,
+            4 => VideoMode::Mode4,
+            5 => VideoMode::Mode5,
+            6 => VideoMode::Mode6,
+            _ => VideoMode::Mode7,
+        }
+    }
+}
+
+// Camadas que podem ser renderizadas isoladamente para depuração
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Bg1,
+    Bg2,
+    Bg3,
+    Bg4,
+    Sprites,
+}
+
+// Entrada da OAM decodificada
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteInfo {
+    pub x: u16, // 9 bits: o bit 8 vem da tabela alta
+    pub y: u8,
+    pub tile: u8,
+    pub palette: u8,
+    pub priority: u8,
+    pub size: (u16, u16),
+    pub h_flip: bool,
+    pub v_flip: bool,
+    pub name_select: bool, // Segunda tabela de tiles (bit 8 do número do tile)
+}
+
+// Configuração efetiva de um BG no modo de vídeo atual
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BgConfig {
+    pub enabled: bool,
+    pub bpp: u8,
+    pub char_base: u16,    // Em words
+    pub tilemap_base: u16, // Em words
+    pub tilemap_size: u8,  // BGnSC bits 0-1: 32x32, 64x32, 32x64, 64x64 tiles
+    pub tile_size_16: bool,
+    pub scroll_x: u16,
+    pub scroll_y: u16,
+}
+
+// Últimos valores escritos em registradores write-only, só para ferramentas de depuração.
+// Não é uma leitura de hardware: no console esses registradores devolvem open bus
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegisterShadow {
+    pub inidisp: u8,
+    pub obsel: u8,
+    pub bgmode: u8,
+    pub mosaic: u8,
+    pub bg_sc: [u8; 4],
+    pub bg_nba: [u8; 2],
+    pub bg_hscroll: [u16; 4],
+    pub bg_vscroll: [u16; 4],
+    pub tm: u8,
+    pub ts: u8,
+    pub setini: u8,
+}
+
 pub struct Ppu {
     //Timing
     pub scanline: u16,
@@ -22,15 +102,27 @@ pub struct Ppu {
 
     pub video_mode: VideoMode,
     pub brightness: u8,
+    line_brightness: u8, // Brilho travado no início da linha em andamento
     pub forced_blank: bool,
 
     pub bg_enabled: [bool; 4],
     pub bg_mode: [u8; 4],
     pub bg_priority: [u8; 4],
     pub bg_size: [bool; 4],
+    pub bg_tilemap_base: [u16; 4], // Endereços em words (BGnSC)
+    pub bg_tilemap_size: [u8; 4],  // BGnSC bits 0-1
+    pub bg_char_base: [u16; 4], // Endereços em words (BG12NBA/BG34NBA)
 
     pub sprites_enabled: bool,
+    pub sub_bg_enabled: [bool; 4],
+    pub sub_sprites_enabled: bool,
+    pub pseudo_hires: bool,
+    pub overscan: bool,
+    pub scanlines_per_frame: u16, // 262 no NTSC, 312 no PAL
+    pub interlace: bool,
     pub sprite_size: u8,
+    pub sprite_base: u8,
+    pub sprite_name_select: u8,
 
     pub bg_hscroll: [u16; 4],
     pub bg_vscroll: [u16; 4],
@@ -39,14 +131,24 @@ pub struct Ppu {
     pub vram_increment: u16,
 
     pub oam_addr: u16,
+    pub oam_latch: u8, // Primeiro byte de um par da tabela baixa
+    pub oam_reload: u16, // Endereço escrito em $2102/$2103, recarregado no início do VBlank
+    pub oam_priority: bool, // Bit 7 de $2103: rotação de prioridade dos sprites
 
     pub cgram_addr: u16,
 
     pub framebuffer: Vec<u32>,
     pub line_buffer: [u8; 256],
+    layer_line: [u8; 256], // Camada que venceu cada pixel da linha em line_buffer
+    palette_line: [u8; 256], // Bits de paleta do tilemap de cada pixel de BG (direct color)
+    layer_map: Vec<u8>,    // layer_line de cada linha, paralelo ao framebuffer
+    index_map: Vec<u8>,    // Índices de paleta de cada pixel, antes da conversão para RGB
 
     pub nmi_enabled: bool,
     pub nmi_flag: bool,
+    pub h_irq_enabled: bool,    // NMITIMEN bit 4
+    pub v_irq_enabled: bool,    // NMITIMEN bit 5
+    pub auto_joypad_read: bool, // NMITIMEN bit 0
 
     pub inidisp: u8,
     pub obsel: u8,
@@ -57,33 +159,89 @@ pub struct Ppu {
     pub bg_mode_reg: u8,
     pub mosaic: u8,
 
+    pub fixed_color: [u8; 3], // COLDATA ($2132): R, G, B de 5 bits
+    pub direct_color: bool, // CGWSEL ($2130) bit 0
+
     pub vmain: u8,
     pub vmadd: u8,
 
     pub vram_read_buffer: u16,
     pub open_bus: u8,
+
+    pub range_over: bool, // Mais de 32 sprites em uma linha neste frame ($213E bit 6)
+    pub time_over: bool,  // Mais de 34 tiles de sprite em uma linha neste frame ($213E bit 7)
+
+    pub profiling: bool,
+    pub render_nanos: u64,
+    pub skip_render: bool, // Avança o timing sem desenhar (fast-forward)
+    pub strict_access: bool, // Bloqueia VRAM/OAM/CGRAM fora do VBlank e do forced blank
+    render_enabled: bool, // Desligado: só timing, para ROMs de teste de CPU
+
+    shadow: RegisterShadow,
+
+    line_hashes: Vec<u64>,      // Checksum de cada linha do framebuffer no último render
+    dirty_lines: Vec<u16>,      // Linhas que mudaram no frame em andamento
+    last_dirty_lines: Vec<u16>, // Linhas que mudaram no último frame completo
+}
+
+impl Default for Ppu {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Ppu {
+    // HBlank vai do ponto 274 até o ponto 1 da linha seguinte
+    pub const HBLANK_START_DOT: u16 = 274;
+    pub const HBLANK_END_DOT: u16 = 1;
+
+    // VBlank (e o NMI) começa na linha 225 tanto no NTSC quanto no PAL; a linha 224
+    // ainda é ativa mas fica fora do framebuffer de 224 linhas
+    pub const VBLANK_START_LINE: u16 = 225;
+
+    // Limites por scanline da avaliação de sprites
+    pub const SPRITES_PER_LINE: usize = 32;
+    pub const SPRITE_TILES_PER_LINE: usize = 34;
+
+    // Valores do layer_map: bits 0-3 são a camada, bits 4-5 a prioridade do pixel
+    pub const LAYER_BG1: u8 = 0;
+    pub const LAYER_BG2: u8 = 1;
+    pub const LAYER_BG3: u8 = 2;
+    pub const LAYER_BG4: u8 = 3;
+    pub const LAYER_OBJ: u8 = 4;
+    pub const LAYER_BACKDROP: u8 = 5;
+
     pub fn new() -> Self {
         Ppu {
             scanline: 0,
             cycle: 0,
             frame_complete: false,
             vblank: false,
-            hblank: false,
+            hblank: true, // Ponto 0 ainda está no HBlank
 
             video_mode: VideoMode::Mode0,
             brightness: 0,
+            line_brightness: 0,
             forced_blank: true,
 
             bg_enabled: [false; 4],
             bg_mode: [0; 4],
             bg_priority: [0; 4],
             bg_size: [false; 4],
+            bg_tilemap_base: [0; 4],
+            bg_tilemap_size: [0; 4],
+            bg_char_base: [0; 4],
 
             sprites_enabled: false,
+            sub_bg_enabled: [false; 4],
+            sub_sprites_enabled: false,
+            pseudo_hires: false,
+            overscan: false,
+            scanlines_per_frame: 262,
+            interlace: false,
             sprite_size: 0,
+            sprite_base: 0,
+            sprite_name_select: 0,
 
             bg_hscroll: [0; 4],
             bg_vscroll: [0; 4],
@@ -92,13 +250,23 @@ impl Ppu {
             vram_increment: 1,
 
             oam_addr: 0,
+            oam_latch: 0,
+            oam_reload: 0,
+            oam_priority: false,
             cgram_addr: 0,
 
             framebuffer: vec![0; 256 * 224],
             line_buffer: [0; 256],
+            layer_line: [Self::LAYER_BACKDROP; 256],
+            palette_line: [0; 256],
+            layer_map: vec![Self::LAYER_BACKDROP; 256 * 224],
+            index_map: vec![0; 256 * 224],
 
             nmi_enabled: false,
             nmi_flag: false,
+            h_irq_enabled: false,
+            v_irq_enabled: false,
+            auto_joypad_read: false,
 
             inidisp: 0x80,
             obsel: 0,
@@ -107,16 +275,156 @@ impl Ppu {
             oamdata: 0,
             bg_mode_reg: 0,
             mosaic: 0,
+            fixed_color: [0; 3],
+            direct_color: false,
             vmain: 0,
             vmadd: 0,
 
             vram_read_buffer: 0,
             open_bus: 0,
+
+            range_over: false,
+            time_over: false,
+
+            profiling: false,
+            render_nanos: 0,
+            skip_render: false,
+            strict_access: false,
+            render_enabled: true,
+
+            shadow: RegisterShadow {
+                inidisp: 0x80,
+                ..RegisterShadow::default()
+            },
+
+            line_hashes: vec![0; 239],
+            dirty_lines: Vec::new(),
+            last_dirty_lines: Vec::new(),
         }
     }
 
+    // Reset volta ao estado de power-on (forced blank, SETINI e NMITIMEN zerados),
+    // mas mantém a configuração do host: região, modo estrito, render e profiling
     pub fn reset(&mut self) {
+        let scanlines_per_frame = self.scanlines_per_frame;
+        let strict_access = self.strict_access;
+        let render_enabled = self.render_enabled;
+        let profiling = self.profiling;
+
         *self = Self::new();
+
+        self.scanlines_per_frame = scanlines_per_frame;
+        self.strict_access = strict_access;
+        self.render_enabled = render_enabled;
+        self.profiling = profiling;
+    }
+
+    // Sem render as linhas não são desenhadas, mas scanline/dot, VBlank, NMI e os
+    // registradores de status seguem normais. As flags de overflow de sprites ($213E)
+    // saem da avaliação feita no render e ficam zeradas
+    pub fn set_render_enabled(&mut self, enabled: bool) {
+        self.render_enabled = enabled;
+    }
+
+    pub fn render_enabled(&self) -> bool {
+        self.render_enabled
+    }
+
+    // Estado salvo em save states: registradores, latches e posição do feixe.
+    // Framebuffer, caches de render e a configuração do host ficam de fora
+    pub(crate) fn save_state(&self, out: &mut Vec<u8>) {
+        state::write_words(out, &[
+            self.scanline, self.cycle, self.vram_addr, self.vram_increment,
+            self.oam_addr, self.oam_reload, self.cgram_addr, self.vram_read_buffer,
+        ]);
+        state::write_words(out, &self.bg_tilemap_base);
+        state::write_words(out, &self.bg_char_base);
+        state::write_words(out, &self.bg_hscroll);
+        state::write_words(out, &self.bg_vscroll);
+
+        for flag in [
+            self.frame_complete, self.vblank, self.hblank, self.forced_blank,
+            self.sprites_enabled, self.sub_sprites_enabled, self.pseudo_hires,
+            self.overscan, self.interlace, self.oam_priority, self.nmi_enabled,
+            self.nmi_flag, self.h_irq_enabled, self.v_irq_enabled,
+            self.auto_joypad_read, self.direct_color, self.range_over, self.time_over,
+        ] {
+            out.push(flag as u8);
+        }
+        for flags in [self.bg_enabled, self.bg_size, self.sub_bg_enabled] {
+            out.extend(flags.map(u8::from));
+        }
+
+        out.extend_from_slice(&[
+            self.video_mode as u8, self.brightness, self.line_brightness, self.sprite_size, self.sprite_base,
+            self.sprite_name_select, self.oam_latch, self.inidisp, self.obsel,
+            self.oamaddl, self.oamaddh, self.oamdata, self.bg_mode_reg, self.mosaic,
+            self.vmain, self.vmadd, self.open_bus,
+        ]);
+        out.extend_from_slice(&self.bg_mode);
+        out.extend_from_slice(&self.bg_priority);
+        out.extend_from_slice(&self.bg_tilemap_size);
+        out.extend_from_slice(&self.fixed_color);
+
+        let shadow = &self.shadow;
+        out.extend_from_slice(&[shadow.inidisp, shadow.obsel, shadow.bgmode, shadow.mosaic]);
+        out.extend_from_slice(&shadow.bg_sc);
+        out.extend_from_slice(&shadow.bg_nba);
+        state::write_words(out, &shadow.bg_hscroll);
+        state::write_words(out, &shadow.bg_vscroll);
+        out.extend_from_slice(&[shadow.tm, shadow.ts, shadow.setini]);
+    }
+
+    // Inverso de save_state; quem chama garante o tamanho da seção antes
+    pub(crate) fn load_state(&mut self, reader: &mut Reader) -> Option<()> {
+        [
+            self.scanline, self.cycle, self.vram_addr, self.vram_increment,
+            self.oam_addr, self.oam_reload, self.cgram_addr, self.vram_read_buffer,
+        ] = reader.words()?;
+        self.bg_tilemap_base = reader.words()?;
+        self.bg_char_base = reader.words()?;
+        self.bg_hscroll = reader.words()?;
+        self.bg_vscroll = reader.words()?;
+
+        [
+            self.frame_complete, self.vblank, self.hblank, self.forced_blank,
+            self.sprites_enabled, self.sub_sprites_enabled, self.pseudo_hires,
+            self.overscan, self.interlace, self.oam_priority, self.nmi_enabled,
+            self.nmi_flag, self.h_irq_enabled, self.v_irq_enabled,
+            self.auto_joypad_read, self.direct_color, self.range_over, self.time_over,
+        ] = reader.flags()?;
+        self.bg_enabled = reader.flags()?;
+        self.bg_size = reader.flags()?;
+        self.sub_bg_enabled = reader.flags()?;
+
+        let video_mode;
+        [
+            video_mode, self.brightness, self.line_brightness, self.sprite_size, self.sprite_base,
+            self.sprite_name_select, self.oam_latch, self.inidisp, self.obsel,
+            self.oamaddl, self.oamaddh, self.oamdata, self.bg_mode_reg, self.mosaic,
+            self.vmain, self.vmadd, self.open_bus,
+        ] = reader.bytes()?;
+        self.video_mode = VideoMode::from_bits(video_mode);
+        self.bg_mode = reader.bytes()?;
+        self.bg_priority = reader.bytes()?;
+        self.bg_tilemap_size = reader.bytes()?;
+        self.fixed_color = reader.bytes()?;
+
+        let shadow = &mut self.shadow;
+        [shadow.inidisp, shadow.obsel, shadow.bgmode, shadow.mosaic] = reader.bytes()?;
+        shadow.bg_sc = reader.bytes()?;
+        shadow.bg_nba = reader.bytes()?;
+        shadow.bg_hscroll = reader.words()?;
+        shadow.bg_vscroll = reader.words()?;
+        [shadow.tm, shadow.ts, shadow.setini] = reader.bytes()?;
+
+        // O modo pseudo-hires muda a largura do framebuffer
+        let len = self.frame_width() * 224;
+        if self.framebuffer.len() != len {
+            self.framebuffer = vec![0; len];
+        }
+
+        Some(())
     }
 
     pub fn step(&mut self, memory: &mut Memory) -> bool {
@@ -124,193 +432,612 @@ impl Ppu {
 
         self.cycle += 1;
 
+        // O brilho de $2100 vale para a linha inteira: escritas no meio dela só
+        // aparecem a partir da próxima
+        if self.cycle == 1 {
+            self.line_brightness = self.brightness;
+        }
+
         if self.cycle >= 341 {
             self.cycle = 0;
+
+            // Renderiza a linha que acabou de terminar
+            if self.scanline < 224 && !self.forced_blank && !self.skip_render && self.render_enabled {
+                if self.profiling {
+                    let start = Instant::now();
+                    self.render_scanline(memory);
+                    self.render_nanos += start.elapsed().as_nanos() as u64;
+                } else {
+                    self.render_scanline(memory);
+                }
+            }
+
             self.scanline += 1;
 
             match self.scanline {
-                0..=223 => {
-                    if !self.forced_blank {
-                        self.render_scanline(memory);
-                    }
+                0..Self::VBLANK_START_LINE => {
                     self.vblank = false;
                 }
 
-                224 => {
+                Self::VBLANK_START_LINE => {
                     self.vblank = true;
                     self.frame_complete = true;
+                    self.last_dirty_lines = std::mem::take(&mut self.dirty_lines);
+
+                    // Fora do forced blank o endereço interno da OAM volta ao de $2102/$2103;
+                    // em forced blank ele segue de onde a CPU parou
+                    if !self.forced_blank {
+                        self.oam_addr = self.oam_reload;
+                    }
 
+                    if self.auto_joypad_read {
+                        memory.auto_read_joypads();
+                    }
+
+                    // A flag de $4210 sobe mesmo com o NMI desligado
+                    self.nmi_flag = true;
                     if self.nmi_enabled {
-                        self.nmi_flag = true;
                         nmi_triggered = true;
                     }
                 }
 
-                225..=261 => {
-                    self.vblank = true;
-                }
-
-                262 => {
+                line if line >= self.scanlines_per_frame => {
                     self.scanline = 0;
+                    self.vblank = false;
                     self.frame_complete = false;
                     self.nmi_flag = false;
+
+                    // Flags de overflow de sprites valem até o fim do VBlank
+                    self.range_over = false;
+                    self.time_over = false;
                 }
 
-                _ => {}
+                _ => {
+                    self.vblank = true;
+                }
             }
         }
 
-        self.hblank = self.cycle >= 256;
+        self.hblank = self.cycle >= Self::HBLANK_START_DOT || self.cycle < Self::HBLANK_END_DOT;
+
+        nmi_triggered
+    }
+
+    // Avança até o início da próxima scanline, desenhando a atual se for visível.
+    // Para testes do renderer sem CPU; retorna se houve NMI no caminho
+    pub fn step_scanline(&mut self, memory: &mut Memory) -> bool {
+        let line = self.scanline;
+        let mut nmi_triggered = false;
+
+        while self.scanline == line {
+            nmi_triggered |= self.step(memory);
+        }
+
+        nmi_triggered
+    }
+
+    // Avança o beam até a posição (scanline, dot); posições fora do frame são ignoradas
+    pub fn step_to(&mut self, memory: &mut Memory, scanline: u16, dot: u16) -> bool {
+        if scanline >= self.scanlines_per_frame || dot >= 341 {
+            return false;
+        }
+
+        let mut nmi_triggered = false;
+        while (self.scanline, self.cycle) != (scanline, dot) {
+            nmi_triggered |= self.step(memory);
+        }
 
         nmi_triggered
     }
 
     fn render_scanline(&mut self, memory: &mut Memory) {
+        let main_line = self.render_screen(memory, self.bg_enabled, self.sprites_enabled);
+        let main_layers = self.layer_line;
+        let main_palettes = self.palette_line;
+        let row = (self.scanline as usize) * self.frame_width();
+
+        if self.layer_map.len() != self.framebuffer.len() {
+            self.layer_map = vec![Self::LAYER_BACKDROP; self.framebuffer.len()];
+            self.index_map = vec![0; self.framebuffer.len()];
+        }
+
+        if self.pseudo_hires {
+            // Pseudo-hires: colunas pares vêm da sub screen, ímpares da main screen
+            let sub_line = self.render_screen(memory, self.sub_bg_enabled, self.sub_sprites_enabled);
+            let sub_layers = self.layer_line;
+            let sub_palettes = self.palette_line;
+
+            for (x, (&sub, &main)) in sub_line.iter().zip(main_line.iter()).enumerate() {
+                let fb_index = row + x * 2;
+                if fb_index + 1 < self.framebuffer.len() {
+                    self.framebuffer[fb_index] = self.output_color(memory, sub, sub_layers[x], sub_palettes[x]);
+                    self.framebuffer[fb_index + 1] =
+                        self.output_color(memory, main, main_layers[x], main_palettes[x]);
+                    self.layer_map[fb_index] = sub_layers[x];
+                    self.layer_map[fb_index + 1] = main_layers[x];
+                    self.index_map[fb_index] = sub;
+                    self.index_map[fb_index + 1] = main;
+                }
+            }
+
+            self.line_buffer = main_line;
+            self.layer_line = main_layers;
+            self.palette_line = main_palettes;
+        } else {
+            for (x, &color_index) in main_line.iter().enumerate() {
+                let rgb_color = self.output_color(memory, color_index, main_layers[x], main_palettes[x]);
+                let fb_index = row + x;
+                if fb_index < self.framebuffer.len() {
+                    self.framebuffer[fb_index] = rgb_color;
+                    self.layer_map[fb_index] = main_layers[x];
+                    self.index_map[fb_index] = color_index;
+                }
+            }
+        }
+
+        self.track_dirty_line();
+    }
+
+    // Compara o checksum da linha recém-desenhada com o do frame anterior
+    fn track_dirty_line(&mut self) {
+        let width = self.frame_width();
+        let row = self.scanline as usize;
+        let Some(pixels) = self.framebuffer.get(row * width..(row + 1) * width) else {
+            return;
+        };
+
+        let mut hasher = DefaultHasher::new();
+        pixels.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        if let Some(stored) = self.line_hashes.get_mut(row)
+            && *stored != hash
+        {
+            *stored = hash;
+            self.dirty_lines.push(self.scanline);
+        }
+    }
+
+    // Camada vencedora de cada pixel do framebuffer (LAYER_* | prioridade << 4),
+    // para diagnosticar bugs de prioridade
+    pub fn layer_map(&self) -> Vec<u8> {
+        self.layer_map.clone()
+    }
+
+    // Índices de paleta compostos (main screen) da última linha desenhada, antes do
+    // lookup na CGRAM: hosts podem aplicar a própria paleta
+    pub fn last_line_indices(&self) -> &[u8; 256] {
+        &self.line_buffer
+    }
+
+    // Mesmos índices acumulados no frame inteiro, paralelo ao framebuffer
+    // (em pseudo-hires as colunas pares vêm da sub screen)
+    pub fn frame_indices(&self) -> &[u8] {
+        &self.index_map
+    }
+
+    // Scanlines que mudaram no último frame completo, para uploads parciais de textura
+    pub fn dirty_scanlines(&self) -> Vec<u16> {
+        self.last_dirty_lines.clone()
+    }
+
+    // Compõe uma linha com as camadas habilitadas em uma das telas (main/sub)
+    fn render_screen(&mut self, memory: &Memory, bg_enabled: [bool; 4], sprites_enabled: bool) -> [u8; 256] {
         self.line_buffer.fill(0);
+        self.layer_line.fill(Self::LAYER_BACKDROP);
+        self.palette_line.fill(0);
 
         match self.video_mode {
             VideoMode::Mode0 => {
-                for bg in 0..4 {
-                    if self.bg_enabled[bg] {
+                for (bg, &enabled) in bg_enabled.iter().enumerate() {
+                    if enabled {
                         self.render_bg_mode0(memory, bg);
                     }
                 }
             }
 
+            // Só o BG1 de 256 cores por enquanto
+            VideoMode::Mode3 | VideoMode::Mode4 if bg_enabled[0] => {
+                self.render_bg_8bpp(memory, 0);
+            }
+
             _ => {
                 // Other modes not implemented yet
             }
         }
 
-        if self.sprites_enabled {
+        if sprites_enabled {
             self.render_sprites(memory);
         }
 
-        for x in 0..256 {
-            let color_index = self.line_buffer[x];
-            let rgb_color = self.get_color_from_cgram(memory, color_index);
-            let fb_index = (self.scanline as usize) * 256 + x;
-            if fb_index < self.framebuffer.len() {
-                self.framebuffer[fb_index] = rgb_color;
+        self.line_buffer
+    }
+
+    // Renderiza só uma camada na área visível inteira, sem prioridade nem outras camadas
+    pub fn render_layer(&mut self, layer: Layer, memory: &Memory) -> Vec<u32> {
+        let saved_scanline = self.scanline;
+        let saved_line = self.line_buffer;
+        let saved_layers = self.layer_line;
+        let saved_palettes = self.palette_line;
+
+        let mut bg_enabled = [false; 4];
+        match layer {
+            Layer::Bg1 => bg_enabled[0] = true,
+            Layer::Bg2 => bg_enabled[1] = true,
+            Layer::Bg3 => bg_enabled[2] = true,
+            Layer::Bg4 => bg_enabled[3] = true,
+            Layer::Sprites => {}
+        }
+        let sprites_enabled = layer == Layer::Sprites;
+
+        let mut buffer = vec![0; 256 * 224];
+        for line in 0..224 {
+            self.scanline = line;
+            let pixels = self.render_screen(memory, bg_enabled, sprites_enabled);
+
+            for (x, &color_index) in pixels.iter().enumerate() {
+                buffer[line as usize * 256 + x] =
+                    self.layer_pixel_color(memory, color_index, self.layer_line[x], self.palette_line[x]);
             }
         }
+
+        self.scanline = saved_scanline;
+        self.line_buffer = saved_line;
+        self.layer_line = saved_layers;
+        self.palette_line = saved_palettes;
+        buffer
+    }
+
+    // Largura do framebuffer: 512 em pseudo-hires, 256 no modo normal
+    pub fn frame_width(&self) -> usize {
+        if self.pseudo_hires { 512 } else { 256 }
+    }
+
+    pub fn set_region(&mut self, region: Region) {
+        self.scanlines_per_frame = match region {
+            Region::Ntsc => 262,
+            Region::Pal => 312,
+        };
+    }
+
+    // Resolução de saída no modo atual: 239 linhas com overscan, dobradas no entrelaçado
+    pub fn active_resolution(&self) -> (u32, u32) {
+        let mut height = if self.overscan { 239 } else { 224 };
+        if self.interlace {
+            height *= 2;
+        }
+
+        (self.frame_width() as u32, height)
+    }
+
+    // Aspecto do pixel (PAR): 8:7 na resolução base, ajustado por hi-res e entrelaçado
+    pub fn aspect_ratio(&self) -> (u32, u32) {
+        match (self.pseudo_hires, self.interlace) {
+            (false, false) => (8, 7),
+            (true, false) => (4, 7),
+            (false, true) => (16, 7),
+            (true, true) => (8, 7),
+        }
     }
 
     fn render_bg_mode0(&mut self, memory: &Memory, bg_layer: usize) {
         let scroll_x = self.bg_hscroll[bg_layer];
         let scroll_y = self.bg_vscroll[bg_layer];
 
-        let y_pos = (self.scanline as u16 + scroll_y) % 256;
+        let y_pos = (self.scanline + scroll_y) % 256;
         let tile_y = y_pos / 8;
         let pixel_y = y_pos % 8;
 
+        // No modo 0 cada BG usa sua própria faixa de 32 cores
+        let palette_base = (bg_layer * 32) as u8;
+
         for tile_x in 0..32 {
             let x_pos = (tile_x * 8 + scroll_x) % 256;
 
-            let tile_index = self.get_bg_tile_index(memory, bg_layer, tile_x as u16, tile_y);
-            let tile_data = self.get_tile_data(memory, tile_index, pixel_y);
+            // Bits 0-9 da entrada do tilemap: número do tile (2bpp = 8 words por tile); bit 13: prioridade
+            let entry = self.get_bg_tile_index(memory, bg_layer, tile_x, tile_y);
+            let tile_index = entry & 0x3FF;
+            let layer = bg_layer as u8 | (((entry >> 13) & 0x01) as u8) << 4;
+            let tile_addr = self.bg_char_base[bg_layer].wrapping_add(tile_index * 8);
+            let pixels = self.get_tile_data(memory, tile_addr, pixel_y, 2);
 
-            for pixel_x in 0..8 {
-                let screen_x = ((x_pos + pixel_x) % 256) as usize;
-                
-                if screen_x < 256 {
-                    let color_index = (tile_data >> (pixel_x * 2)) & 0x03;
-                    if color_index != 0 {
-                        self.line_buffer[screen_x] = color_index as u8;
-                    }
+            for (pixel_x, &color_index) in pixels.iter().enumerate() {
+                let screen_x = ((x_pos as usize) + pixel_x) % 256;
+
+                if color_index != 0 {
+                    self.line_buffer[screen_x] = palette_base + color_index;
+                    self.layer_line[screen_x] = layer;
                 }
             }
         }
     }
 
-    fn get_bg_tile_index(&self, memory: &Memory, bg_layer: usize, tile_x: u16, tile_y: u16) -> u16 {
-        let tilemap_addr = 0x0000 + (bg_layer * 0x800);
-        let tile_addr = tilemap_addr + ((tile_y * 32 + tile_x) * 2) as usize;
+    // BG de 8bpp: o pixel é o índice direto na CGRAM (ou a cor, em direct color).
+    // Os bits 10-12 da entrada do tilemap ficam em palette_line para o direct color
+    fn render_bg_8bpp(&mut self, memory: &Memory, bg_layer: usize) {
+        let scroll_x = self.bg_hscroll[bg_layer];
+        let scroll_y = self.bg_vscroll[bg_layer];
 
-        if tile_addr < memory.vram.len() {
-            let low = memory.vram[tile_addr] as u16;
-            let high = memory.vram[tile_addr + 1] as u16;
-            (high << 8) | low
-        } else {
-            0
+        let y_pos = (self.scanline + scroll_y) % 256;
+        let tile_y = y_pos / 8;
+        let pixel_y = y_pos % 8;
+
+        for tile_x in 0..32 {
+            let x_pos = (tile_x * 8 + scroll_x) % 256;
+
+            // 8bpp = 32 words por tile
+            let entry = self.get_bg_tile_index(memory, bg_layer, tile_x, tile_y);
+            let tile_index = entry & 0x3FF;
+            let palette = ((entry >> 10) & 0x07) as u8;
+            let layer = bg_layer as u8 | (((entry >> 13) & 0x01) as u8) << 4;
+            let tile_addr = self.bg_char_base[bg_layer].wrapping_add(tile_index * 32);
+            let pixels = self.get_tile_data(memory, tile_addr, pixel_y, 8);
+
+            for (pixel_x, &color_index) in pixels.iter().enumerate() {
+                let screen_x = ((x_pos as usize) + pixel_x) % 256;
+
+                if color_index != 0 {
+                    self.line_buffer[screen_x] = color_index;
+                    self.layer_line[screen_x] = layer;
+                    self.palette_line[screen_x] = palette;
+                }
+            }
         }
     }
 
-    fn get_tile_data(&self, memory: &Memory, tile_index: u16, pixel_row: u16) -> u32 {
-        let tile_addr = (tile_index * 32 + pixel_row * 4) as usize;
-
-        if tile_addr + 3 < memory.vram.len() {
-            let plane0 = memory.vram[tile_addr] as u32;
-            let plane1 = memory.vram[tile_addr + 1] as u32;
-            let plane2 = memory.vram[tile_addr + 2] as u32;
-            let plane3 = memory.vram[tile_addr + 3] as u32;
+    // Entrada do tilemap (em words) do BG na posição de tile indicada
+    pub fn get_bg_tile_index(&self, memory: &Memory, bg_layer: usize, tile_x: u16, tile_y: u16) -> u16 {
+        let addr = self.bg_tilemap_base[bg_layer].wrapping_add(tile_y * 32 + tile_x);
+        memory.vram_word(addr)
+    }
 
-            let mut pixel_data = 0;
-            for bit in 0..8 {
-                let color = ((plane0 >> bit) & 1) |
-                            ((plane1 >> bit) & 1) << 1 |
-                            ((plane2 >> bit) & 1) << 2 |
-                            ((plane3 >> bit) & 1) << 3;
-                pixel_data |= color << (bit * 4);      
+    // Decodifica uma linha de um tile planar (2, 4 ou 8bpp) em índices de paleta,
+    // na ordem da tela (bit 7 = pixel mais à esquerda). Cada word de uma linha guarda
+    // um par de planos, e cada par de planos ocupa 8 words
+    pub fn get_tile_data(&self, memory: &Memory, tile_addr: u16, pixel_row: u16, bpp: u8) -> [u8; 8] {
+        let mut pixels = [0; 8];
+
+        for pair in 0..(bpp as u16 / 2) {
+            let word = memory.vram_word(tile_addr.wrapping_add(pair * 8 + pixel_row));
+            let low = word as u8;
+            let high = (word >> 8) as u8;
+
+            for (x, pixel) in pixels.iter_mut().enumerate() {
+                let bit = 7 - x;
+                *pixel |= ((low >> bit) & 1) << (pair * 2);
+                *pixel |= ((high >> bit) & 1) << (pair * 2 + 1);
             }
+        }
 
-            pixel_data
-        } else {
-            0
+        pixels
+    }
+
+    // VRAM inteira decodificada como tiles de 8x8 (índices de paleta, linha a linha).
+    // Cada tile ocupa 4 * bpp words; bpp fora de 2/4/8 devolve uma lista vazia
+    pub fn decode_all_tiles(&self, memory: &Memory, bpp: u8) -> Vec<[u8; 64]> {
+        if !matches!(bpp, 2 | 4 | 8) {
+            return Vec::new();
+        }
+
+        let tile_words = 4 * bpp as usize;
+        (0..0x8000 / tile_words)
+            .map(|tile| {
+                let mut pixels = [0; 64];
+                for row in 0..8 {
+                    let line = self.get_tile_data(memory, (tile * tile_words) as u16, row as u16, bpp);
+                    pixels[row * 8..row * 8 + 8].copy_from_slice(&line);
+                }
+                pixels
+            })
+            .collect()
+    }
+
+    // Tamanhos (pequeno, grande) selecionados pelos bits 5-7 do OBSEL ($2101)
+    pub fn sprite_dimensions(&self, large: bool) -> (u16, u16) {
+        let (small, big) = match self.sprite_size {
+            0 => ((8, 8), (16, 16)),
+            1 => ((8, 8), (32, 32)),
+            2 => ((8, 8), (64, 64)),
+            3 => ((16, 16), (32, 32)),
+            4 => ((16, 16), (64, 64)),
+            5 => ((32, 32), (64, 64)),
+            6 => ((16, 32), (32, 64)),
+            _ => ((16, 32), (32, 32)),
+        };
+
+        if large { big } else { small }
+    }
+
+    // Bits por pixel de cada BG no modo atual; None se o modo não tem esse BG
+    pub fn bg_bpp(&self, layer: usize) -> Option<u8> {
+        let bpp: &[u8] = match self.video_mode {
+            VideoMode::Mode0 => &[2, 2, 2, 2],
+            VideoMode::Mode1 => &[4, 4, 2],
+            VideoMode::Mode2 => &[4, 4],
+            VideoMode::Mode3 => &[8, 4],
+            VideoMode::Mode4 => &[8, 2],
+            VideoMode::Mode5 => &[4, 2],
+            VideoMode::Mode6 => &[4],
+            VideoMode::Mode7 => &[8],
+        };
+
+        bpp.get(layer).copied()
+    }
+
+    // Configuração de um BG (0-3) reunida dos registradores, para ferramentas
+    pub fn bg_config(&self, layer: usize) -> Option<BgConfig> {
+        let bpp = self.bg_bpp(layer)?;
+
+        Some(BgConfig {
+            enabled: self.bg_enabled[layer],
+            bpp,
+            char_base: self.bg_char_base[layer],
+            tilemap_base: self.bg_tilemap_base[layer],
+            tilemap_size: self.bg_tilemap_size[layer],
+            tile_size_16: self.bg_size[layer],
+            scroll_x: self.bg_hscroll[layer],
+            scroll_y: self.bg_vscroll[layer],
+        })
+    }
+
+    // Decodifica uma entrada da OAM: 4 bytes na tabela baixa + 2 bits na tabela alta
+    pub fn sprite_info(&self, memory: &Memory, sprite: usize) -> SpriteInfo {
+        let oam_addr = sprite * 4;
+
+        // Tabela alta: 2 bits por sprite (bit 0 = bit 8 do X, bit 1 = tamanho)
+        let high_bits = memory.oam[0x200 + sprite / 4] >> ((sprite % 4) * 2);
+        let x_high = (high_bits & 0x01) as u16;
+        let large = (high_bits & 0x02) != 0;
+        let attr = memory.oam[oam_addr + 3];
+
+        SpriteInfo {
+            x: memory.oam[oam_addr] as u16 | (x_high << 8),
+            y: memory.oam[oam_addr + 1],
+            tile: memory.oam[oam_addr + 2],
+            palette: (attr >> 1) & 0x07,
+            priority: (attr >> 4) & 0x03,
+            size: self.sprite_dimensions(large),
+            h_flip: (attr & 0x40) != 0,
+            v_flip: (attr & 0x80) != 0,
+            name_select: (attr & 0x01) != 0,
         }
     }
 
+    // Lista dos 128 sprites, para overlays de depuração
+    pub fn sprites(&self, memory: &Memory) -> Vec<SpriteInfo> {
+        (0..128).map(|sprite| self.sprite_info(memory, sprite)).collect()
+    }
+
     fn render_sprites(&mut self, memory: &Memory) {
-        for sprite in 0..128 {
-            let oam_addr = sprite * 4;
-
-            if oam_addr + 3 < memory.oam.len() {
-                let x = memory.oam[oam_addr] as u16;
-                let y = memory.oam[oam_addr + 1] as u16;
-                let tile = memory.oam[oam_addr + 2] as u16;
-                let attr = memory.oam[oam_addr + 3];
-
-                if y <= self.scanline && self.scanline < y + 8 {
-                    let sprite_y = self.scanline - y;
-                    let sprite_data = self.get_sprite_data(memory, tile, sprite_y);
-
-                    for pixel_x in 0..8 {
-                        let screen_x = (x + pixel_x) as usize;
-
-                        if screen_x < 256 {
-                            let color_index = (sprite_data >> (pixel_x * 4)) & 0x0F;
-                            if color_index != 0 {
-                                self.line_buffer[screen_x] = color_index as u8 + 16;
-                            }
-                        }
+        let mut sprites_on_line = 0;
+        let mut tiles_on_line = 0;
+
+        'sprites: for sprite in 0..128 {
+            let info = self.sprite_info(memory, sprite);
+            let tile = info.tile as u16;
+            let name_table = info.name_select as u16;
+            let (width, height) = info.size;
+
+            // Y é de 8 bits: a diferença com wrap faz sprites em Y=0xF0-0xFF entrarem pelo topo
+            let sprite_y = (self.scanline as u8).wrapping_sub(info.y) as u16;
+            if self.scanline >= 256 || sprite_y >= height {
+                continue;
+            }
+
+            if sprites_on_line == Self::SPRITES_PER_LINE {
+                self.range_over = true;
+                break;
+            }
+            sprites_on_line += 1;
+
+            let tile_row = sprite_y / 8;
+            let pixel_row = sprite_y % 8;
+
+            for tile_col in 0..(width / 8) {
+                // Tiles totalmente fora da tela não são buscados
+                let tile_x = (info.x + tile_col * 8) & 0x1FF;
+                if (256..=0x1F8).contains(&tile_x) {
+                    continue;
+                }
+
+                if tiles_on_line == Self::SPRITE_TILES_PER_LINE {
+                    self.time_over = true;
+                    break 'sprites;
+                }
+                tiles_on_line += 1;
+
+                let tile_index = (((tile >> 4) + tile_row) & 0x0F) << 4 | ((tile + tile_col) & 0x0F);
+                let tile_addr = self.sprite_tile_address((name_table << 8) | tile_index);
+                let pixels = self.get_tile_data(memory, tile_addr, pixel_row, 4);
+
+                for (pixel_x, &color_index) in pixels.iter().enumerate() {
+                    // X de 9 bits: valores >= 256 ficam à esquerda da tela
+                    let screen_x = (info.x + tile_col * 8 + pixel_x as u16) & 0x1FF;
+
+                    // Entre sprites vence o menor índice de OAM: um pixel já marcado como OBJ
+                    // no layer_line não é sobrescrito, qualquer que seja a prioridade
+                    if screen_x < 256 && color_index != 0
+                        && (self.layer_line[screen_x as usize] & 0x0F) != Self::LAYER_OBJ
+                    {
+                        self.line_buffer[screen_x as usize] = color_index + 16;
+                        self.layer_line[screen_x as usize] = Self::LAYER_OBJ | info.priority << 4;
                     }
                 }
             }
         }
     }
 
-    fn get_sprite_data(&self, memory: &Memory, tile_index: u16, pixel_row: u16) -> u32 {
-        let tile_addr = (0x4000 + tile_index * 32 + pixel_row * 4) as usize;
+    // Endereço (em words) do tile de sprite: base em passos de 8K words e,
+    // para a segunda tabela (bit 8 do tile), gap de (N+1) * 4K words
+    pub fn sprite_tile_address(&self, tile: u16) -> u16 {
+        let mut addr = (self.sprite_base as u16) << 13;
 
-        if tile_addr + 3 < memory.vram.len() {
-            let plane0 = memory.vram[tile_addr] as u32;
-            let plane1 = memory.vram[tile_addr + 1] as u32;
-            let plane2 = memory.vram[tile_addr + 2] as u32;
-            let plane3 = memory.vram[tile_addr + 3] as u32;
+        if (tile & 0x100) != 0 {
+            addr = addr.wrapping_add((self.sprite_name_select as u16 + 1) << 12);
+        }
 
-            let mut pixel_data = 0;
-            for bit in 0..8 {
-                let color = ((plane0 >> bit) & 1) |
-                            ((plane1 >> bit) & 1) << 1 |
-                            ((plane2 >> bit) & 1) << 2 |
-                            ((plane3 >> bit) & 1) << 3;
-                pixel_data |= color << (bit * 4);      
-            }
-            pixel_data
+        addr.wrapping_add((tile & 0xFF) * 16) & 0x7FFF
+    }
+
+    // Cor decodificada (RGB888) de uma entrada da paleta, sem renderizar
+    pub fn palette_color(&self, index: u8, memory: &Memory) -> u32 {
+        self.get_color_from_cgram(memory, index)
+    }
 
+    pub fn palette_rgb_table(&self, memory: &Memory) -> [u32; 256] {
+        let mut table = [0; 256];
+        for (index, color) in table.iter_mut().enumerate() {
+            *color = self.get_color_from_cgram(memory, index as u8);
+        }
+        table
+    }
+
+    // Cor de um pixel de BG: direct color no BG1 de 256 cores (modos 3, 4 e 7) ou lookup na CGRAM
+    pub fn bg_pixel_color(&self, memory: &Memory, bg: usize, pixel: u8, palette: u8) -> u32 {
+        let direct = self.direct_color
+            && bg == 0
+            && matches!(self.video_mode, VideoMode::Mode3 | VideoMode::Mode4 | VideoMode::Mode7);
+
+        if direct && pixel != 0 {
+            Self::direct_color_rgb(pixel, palette)
+        } else {
+            self.get_color_from_cgram(memory, pixel)
+        }
+    }
+
+    // Pixel BBGGGRRR + bits da paleta (bgr) montam uma cor de 15 bits sem passar pela CGRAM
+    pub fn direct_color_rgb(pixel: u8, palette: u8) -> u32 {
+        let r = ((pixel & 0x07) << 2) | ((palette & 0x01) << 1);
+        let g = (((pixel >> 3) & 0x07) << 2) | (palette & 0x02);
+        let b = (((pixel >> 6) & 0x03) << 3) | (palette & 0x04);
+
+        ((r as u32) << 19) | ((g as u32) << 11) | ((b as u32) << 3)
+    }
+
+    // Pixels de BG passam por bg_pixel_color (direct color); sprites e backdrop pela CGRAM
+    fn layer_pixel_color(&self, memory: &Memory, color_index: u8, layer: u8, palette: u8) -> u32 {
+        let bg = (layer & 0x0F) as usize;
+        if bg < 4 {
+            self.bg_pixel_color(memory, bg, color_index, palette)
         } else {
-            0
+            self.get_color_from_cgram(memory, color_index)
+        }
+    }
+
+    // Cor final do framebuffer: cor da camada vencedora escalada pelo brilho travado da linha
+    fn output_color(&self, memory: &Memory, color_index: u8, layer: u8, palette: u8) -> u32 {
+        Self::apply_brightness(self.layer_pixel_color(memory, color_index, layer, palette), self.line_brightness)
+    }
+
+    // Brilho 15 mantém a cor, 0 apaga
+    pub fn apply_brightness(rgb: u32, brightness: u8) -> u32 {
+        let brightness = (brightness & 0x0F) as u32;
+        if brightness == 15 {
+            return rgb;
         }
+
+        let scale = |shift: u32| (((rgb >> shift) & 0xFF) * brightness / 15) << shift;
+        scale(16) | scale(8) | scale(0)
     }
 
     fn get_color_from_cgram(&self, memory: &Memory, color_index: u8) -> u32 {
@@ -336,6 +1063,8 @@ impl Ppu {
     }
 
     pub fn write_register(&mut self, addr: u16, value: u8) {
+        self.record_shadow(addr, value);
+
         match addr {
             0x2100 => {
                 self.brightness = value & 0x0F;
@@ -343,21 +1072,14 @@ impl Ppu {
             }
 
             0x2101 => {
-                self.sprite_size = value & 0x07;
+                self.obsel = value;
+                self.sprite_size = (value >> 5) & 0x07;
+                self.sprite_name_select = (value >> 3) & 0x03;
+                self.sprite_base = value & 0x07;
             }
 
             0x2105 => {
-                self.video_mode = match value & 0x07 {
-                    0 => VideoMode::Mode0,
-                    1 => VideoMode::Mode1,
-                    2 => VideoMode::Mode2,
-                    3 => VideoMode::Mode3,
-                    4 => VideoMode::Mode4,
-                    5 => VideoMode::Mode5,
-                    6 => VideoMode::Mode6,
-                    7 => VideoMode::Mode7,
-                    _ => VideoMode::Mode0,
-                };
+                self.video_mode = VideoMode::from_bits(value);
 
                 self.bg_size[0] = (value & 0x10) != 0;
                 self.bg_size[1] = (value & 0x20) != 0;
@@ -365,6 +1087,20 @@ impl Ppu {
                 self.bg_size[3] = (value & 0x80) != 0;
             }
 
+            // BGnSC: bits 2-7 dão a base do tilemap em passos de 1K words
+            0x2107..=0x210A => {
+                let bg = (addr - 0x2107) as usize;
+                self.bg_tilemap_base[bg] = ((value & 0xFC) as u16) << 8;
+                self.bg_tilemap_size[bg] = value & 0x03;
+            }
+
+            // BG12NBA/BG34NBA: um nibble por BG, base dos tiles em passos de 4K words
+            0x210B | 0x210C => {
+                let bg = ((addr - 0x210B) * 2) as usize;
+                self.bg_char_base[bg] = ((value & 0x0F) as u16) << 12;
+                self.bg_char_base[bg + 1] = ((value >> 4) as u16) << 12;
+            }
+
             0x2115 => {
                 self.vmain = value;
                 self.vram_increment = match value & 0x03 {
@@ -388,6 +1124,20 @@ impl Ppu {
                 self.cgram_addr = (value as u16) & 0x1FF;
             }
 
+            0x2130 => {
+                self.direct_color = (value & 0x01) != 0;
+            }
+
+            0x2132 => {
+                // Bits 5-7 selecionam quais canais (R, G, B) recebem a intensidade
+                let intensity = value & 0x1F;
+                for channel in 0..3 {
+                    if (value & (0x20 << channel)) != 0 {
+                        self.fixed_color[channel] = intensity;
+                    }
+                }
+            }
+
             0x212C => {
                 self.bg_enabled[0] = (value & 0x01) != 0;
                 self.bg_enabled[1] = (value & 0x02) != 0;
@@ -396,14 +1146,61 @@ impl Ppu {
                 self.sprites_enabled = (value & 0x10) != 0;
             }
 
+            0x212D => {
+                for bg in 0..4 {
+                    self.sub_bg_enabled[bg] = (value & (1 << bg)) != 0;
+                }
+                self.sub_sprites_enabled = (value & 0x10) != 0;
+            }
+
+            0x2133 => {
+                self.overscan = (value & 0x04) != 0;
+                self.interlace = (value & 0x01) != 0;
+
+                let pseudo_hires = (value & 0x08) != 0;
+                if pseudo_hires != self.pseudo_hires {
+                    self.pseudo_hires = pseudo_hires;
+                    self.framebuffer = vec![0; self.frame_width() * 224];
+                }
+            }
+
             0x4200 => {
                 self.nmi_enabled = (value & 0x80) != 0;
+                self.v_irq_enabled = (value & 0x20) != 0;
+                self.h_irq_enabled = (value & 0x10) != 0;
+                self.auto_joypad_read = (value & 0x01) != 0;
             }
 
             _ => {}
         }
     }
 
+    fn record_shadow(&mut self, addr: u16, value: u8) {
+        let shadow = &mut self.shadow;
+
+        match addr {
+            0x2100 => shadow.inidisp = value,
+            0x2101 => shadow.obsel = value,
+            0x2105 => shadow.bgmode = value,
+            0x2106 => shadow.mosaic = value,
+            0x2107..=0x210A => shadow.bg_sc[(addr - 0x2107) as usize] = value,
+            0x210B | 0x210C => shadow.bg_nba[(addr - 0x210B) as usize] = value,
+            0x212C => shadow.tm = value,
+            0x212D => shadow.ts = value,
+            0x2133 => shadow.setini = value,
+            _ => {}
+        }
+    }
+
+    // Configuração escrita pelo jogo; os scrolls vêm do estado atual da PPU
+    pub fn shadow_registers(&self) -> RegisterShadow {
+        RegisterShadow {
+            bg_hscroll: self.bg_hscroll,
+            bg_vscroll: self.bg_vscroll,
+            ..self.shadow
+        }
+    }
+
     pub fn read_register(&mut self, addr: u16) -> u8 {
         match addr {
 
@@ -420,9 +1217,10 @@ impl Ppu {
             }
 
             0x213E => {
+                // Versão do 5C77 no nibble baixo
                 let mut status = 0x01;
-                if self.vblank { status |= 0x80; }
-                if self.hblank { status |= 0x40; }
+                if self.time_over { status |= 0x80; }
+                if self.range_over { status |= 0x40; }
                 status
             }
 
@@ -436,7 +1234,7 @@ impl Ppu {
             0x4210 => {
                 let mut value = 0x02;
 
-                if self.nmi_enabled { value |= 0x80; }
+                if self.nmi_flag { value |= 0x80; }
 
                 self.nmi_flag = false;
 
@@ -464,10 +1262,35 @@ impl Ppu {
         }
     }
 
+    // Cor fixa no formato BGR555 do CGRAM
+    pub fn get_fixed_color(&self) -> u16 {
+        (self.fixed_color[0] as u16) |
+        ((self.fixed_color[1] as u16) << 5) |
+        ((self.fixed_color[2] as u16) << 10)
+    }
+
     pub fn get_framebuffer(&self) -> &[u32] {
         &self.framebuffer
     }
 
+    // Copia o frame para um buffer do chamador, que pode ser reaproveitado entre frames
+    pub fn render_into(&self, buffer: &mut [u32]) -> Result<(), RenderError> {
+        if buffer.len() != self.framebuffer.len() {
+            return Err(RenderError::BufferSize {
+                expected: self.framebuffer.len(),
+                actual: buffer.len(),
+            });
+        }
+
+        buffer.copy_from_slice(&self.framebuffer);
+        Ok(())
+    }
+
+    // No modo estrito a CPU só acessa VRAM/OAM/CGRAM no VBlank ou em forced blank
+    pub fn memory_access_blocked(&self) -> bool {
+        self.strict_access && !self.vblank && !self.forced_blank
+    }
+
     pub fn frame_ready(&mut self) -> bool {
         if self.frame_complete {
             self.frame_complete = false;
@@ -0,0 +1,59 @@
+use crate::system::System;
+use std::collections::VecDeque;
+
+// Rewind sobre save states: um snapshot a cada `interval` frames, guardados em um
+// buffer circular de `capacity` entradas (o mais antigo é descartado)
+pub struct Rewind {
+    snapshots: VecDeque<Vec<u8>>,
+    capacity: usize,
+    interval: u32,
+    frames: u32,
+}
+
+impl Rewind {
+    pub fn new(capacity: usize, interval: u32) -> Self {
+        Rewind {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity,
+            interval: interval.max(1),
+            frames: 0,
+        }
+    }
+
+    // Chamado uma vez por frame; só grava quando o intervalo fecha
+    pub fn capture(&mut self, system: &System) {
+        self.frames += 1;
+        if self.capacity == 0 || self.frames < self.interval {
+            return;
+        }
+        self.frames = 0;
+
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(system.save_state());
+    }
+
+    // Restaura o snapshot mais recente e o remove; false se o buffer está vazio
+    pub fn rewind_one_frame(&mut self, system: &mut System) -> bool {
+        self.frames = 0;
+
+        match self.snapshots.pop_back() {
+            Some(snapshot) => system.load_state(&snapshot).is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.frames = 0;
+    }
+}
@@ -0,0 +1,162 @@
+// Formato de save state: cabeçalho + seções (tag, tamanho, dados)
+// e comparação de memória entre dois snapshots
+
+pub(crate) const STATE_MAGIC: &[u8; 4] = b"SNSS";
+pub(crate) const STATE_VERSION: u8 = 2;
+
+// Tags das seções gravadas por System::save_state
+pub(crate) const SECTION_CPU: u8 = b'C';
+pub(crate) const SECTION_WRAM: u8 = b'W';
+pub(crate) const SECTION_SRAM: u8 = b'S';
+pub(crate) const SECTION_VRAM: u8 = b'V';
+pub(crate) const SECTION_CGRAM: u8 = b'G';
+pub(crate) const SECTION_OAM: u8 = b'O';
+pub(crate) const SECTION_PPU: u8 = b'P'; // Registradores internos e posição do feixe
+pub(crate) const SECTION_IO: u8 = b'I';  // Registradores de I/O, DMA, portas e controles
+#[cfg(feature = "apu")]
+pub(crate) const SECTION_APU: u8 = b'A'; // SPC700: registradores, RAM, portas e IPL
+
+// Memória comparada por diff_states
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateRegion {
+    Wram,
+    Sram,
+    Vram,
+}
+
+// Um byte que mudou entre dois snapshots. WRAM usa endereços a partir de $7E:0000;
+// SRAM e VRAM usam offsets dentro dos próprios buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemDiff {
+    pub region: StateRegion,
+    pub addr: u32,
+    pub before: u8,
+    pub after: u8,
+}
+
+pub(crate) fn write_header(out: &mut Vec<u8>) {
+    out.extend_from_slice(STATE_MAGIC);
+    out.push(STATE_VERSION);
+}
+
+pub(crate) fn write_section(out: &mut Vec<u8>, tag: u8, data: &[u8]) {
+    out.push(tag);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+// Seções de um save state; None se o cabeçalho ou algum tamanho for inválido
+pub(crate) fn sections(state: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let header_len = STATE_MAGIC.len() + 1;
+    if state.len() < header_len || &state[..4] != STATE_MAGIC || state[4] != STATE_VERSION {
+        return None;
+    }
+
+    let mut sections = Vec::new();
+    let mut rest = &state[header_len..];
+
+    while !rest.is_empty() {
+        if rest.len() < 5 {
+            return None;
+        }
+
+        let tag = rest[0];
+        let len = u32::from_le_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+        let data = rest.get(5..5 + len)?;
+
+        sections.push((tag, data));
+        rest = &rest[5 + len..];
+    }
+
+    Some(sections)
+}
+
+// Leitura sequencial dos campos de uma seção (little-endian); None quando os dados acabam
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    pub(crate) fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, rest) = self.data.split_first_chunk::<N>()?;
+        self.data = rest;
+        Some(*head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Option<u8> {
+        self.bytes::<1>().map(|[value]| value)
+    }
+
+    pub(crate) fn u16(&mut self) -> Option<u16> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    #[cfg(feature = "apu")]
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        self.bytes().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn flags<const N: usize>(&mut self) -> Option<[bool; N]> {
+        self.bytes::<N>().map(|bytes| bytes.map(|value| value != 0))
+    }
+
+    pub(crate) fn words<const N: usize>(&mut self) -> Option<[u16; N]> {
+        let mut words = [0; N];
+        for word in words.iter_mut() {
+            *word = self.u16()?;
+        }
+        Some(words)
+    }
+}
+
+pub(crate) fn write_words(out: &mut Vec<u8>, words: &[u16]) {
+    for word in words {
+        out.extend_from_slice(&word.to_le_bytes());
+    }
+}
+
+pub(crate) fn section<'a>(sections: &[(u8, &'a [u8])], tag: u8) -> Option<&'a [u8]> {
+    sections.iter().find(|(t, _)| *t == tag).map(|&(_, data)| data)
+}
+
+// Bytes de WRAM/SRAM/VRAM que diferem entre dois save states.
+// Estados inválidos não geram diferenças
+pub fn diff_states(a: &[u8], b: &[u8]) -> Vec<MemDiff> {
+    let (Some(before), Some(after)) = (sections(a), sections(b)) else {
+        return Vec::new();
+    };
+
+    let regions = [
+        (SECTION_WRAM, StateRegion::Wram, 0x7E0000),
+        (SECTION_SRAM, StateRegion::Sram, 0),
+        (SECTION_VRAM, StateRegion::Vram, 0),
+    ];
+
+    let mut diffs = Vec::new();
+    for (tag, region, base) in regions {
+        let (Some(old), Some(new)) = (section(&before, tag), section(&after, tag)) else {
+            continue;
+        };
+
+        for (i, (&old_byte, &new_byte)) in old.iter().zip(new.iter()).enumerate() {
+            if old_byte != new_byte {
+                diffs.push(MemDiff {
+                    region,
+                    addr: base + i as u32,
+                    before: old_byte,
+                    after: new_byte,
+                });
+            }
+        }
+    }
+
+    diffs
+}
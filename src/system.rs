@@ -1,50 +1,571 @@
 use crate::cpu::Cpu;
-use crate::memory::Memory;
-use crate::ppu::Ppu;
+use crate::emulator::JoypadState;
+use crate::memory::{DmaTransfer, Memory, Region, RomType};
+use crate::ppu::{Ppu, RenderError};
+use crate::state;
 use std::cell::RefCell;
+use std::io::Write;
+use std::path::Path;
 use std::rc::Rc;
+use std::time::Instant;
+
+// Tempo gasto em um frame, separado entre execução da CPU e render da PPU
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameProfile {
+    pub cpu_nanos: u64,
+    pub render_nanos: u64,
+    pub cpu_cycles: u64,
+}
+
+// Motivo pelo qual run_until/run_frame parou
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStop {
+    Condition,
+    InfiniteLoop { pc: u32, count: u32 },
+    Timeout { cycles: u64 },
+}
+
+// Como a PPU acompanha os ciclos da CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimingMode {
+    // 4 dots por ciclo, todos aplicados no fim da instrução
+    #[default]
+    InstructionEnd,
+    // Ciclo a ciclo pelo modelo de master cycles (velocidade do barramento, 4 por dot)
+    PerCycle,
+}
+
+// Chamado com o framebuffer a cada frame completo
+pub type FrameCallback = Box<dyn FnMut(&[u32])>;
+
+// Estado de CPU e RAM no formato das suítes single-step de 65816
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CpuTestState {
+    pub pc: u16,
+    pub sp: u16,
+    pub a: u16,
+    pub x: u16,
+    pub y: u16,
+    pub db: u8,
+    pub dp: u16,
+    pub pb: u8,
+    pub p: u8,
+    pub e: bool,
+    pub ram: Vec<(u32, u8)>,
+}
 
 pub struct System {
     pub cpu: Cpu,
     pub ppu: Rc<RefCell<Ppu>>,
     pub memory: Memory,
+
+    rom_path: Option<String>,
+    loop_threshold: u32,
+    cycle_budget: u64, // Ciclos de CPU por chamada antes de Timeout (0 desliga)
+    timing_mode: TimingMode,
+    master_remainder: u32, // Master cycles que ainda não fecharam um dot
+    frame_callback: Option<FrameCallback>,
+
+    profiling: bool,
+    profile_cpu_nanos: u64,
+    profile_start_cycles: u64,
+    last_profile: Option<FrameProfile>,
 }
 
 impl System {
+    // 40 master cycles por scanline, em ciclos de 8 master cycles
+    pub const REFRESH_STALL_CYCLES: u32 = 5;
+
+    // 4 frames PAL: 4 x 312 linhas x 341 dots, a 4 dots por ciclo
+    pub const DEFAULT_CYCLE_BUDGET: u64 = 312 * 341;
+
     pub fn new(rom: Vec<u8>) -> Self {
         let ppu = Rc::new(RefCell::new(Ppu::new()));
+        let memory = Memory::new(rom, Rc::clone(&ppu));
+        Self::with_memory(memory, ppu)
+    }
+
+    // Sistema sobre Memory::flat(), para rodar suítes de teste de CPU
+    pub fn flat() -> Self {
+        let ppu = Rc::new(RefCell::new(Ppu::new()));
+        let memory = Memory::flat_with_ppu(Rc::clone(&ppu));
+        Self::with_memory(memory, ppu)
+    }
+
+    fn with_memory(memory: Memory, ppu: Rc<RefCell<Ppu>>) -> Self {
+        ppu.borrow_mut().set_region(memory.region);
 
         System {
             cpu: Cpu::new(),
-            memory: Memory::new(rom, Rc::clone(&ppu)),
+            memory,
             ppu,
+
+            rom_path: None,
+            loop_threshold: 10,
+            cycle_budget: Self::DEFAULT_CYCLE_BUDGET,
+            timing_mode: TimingMode::default(),
+            master_remainder: 0,
+            frame_callback: None,
+
+            profiling: false,
+            profile_cpu_nanos: 0,
+            profile_start_cycles: 0,
+            last_profile: None,
         }
     }
 
-    pub fn step(&mut self) -> u8 {
-        let opcode = self.memory.read(self.cpu.pc);
-        self.cpu.pc += 1;
+    pub fn new_with_mapping(rom: Vec<u8>, rom_type: RomType) -> Self {
+        let mut system = Self::new(rom);
+        system.memory.set_rom_type(rom_type);
+        let region = system.memory.detect_region();
+        system.set_region(region);
+        system
+    }
 
-        let cycles = self.cpu.execute_instruction(opcode, &mut self.memory);
-        self.cpu.cycles += cycles as u64;
+    // Executa uma instrução; retorna os ciclos gastos, incluindo stalls de DMA e refresh
+    pub fn step(&mut self) -> u32 {
+        // Velocidade do barramento onde a instrução está (FastROM/SlowROM)
+        let access_cycles = self.memory.access_cycles(self.cpu.full_pc());
+        let opcode = self.memory.read(self.cpu.full_pc());
+        self.cpu.advance_pc(1);
+
+        let instruction_cycles = if self.profiling {
+            let start = Instant::now();
+            let cycles = self.cpu.execute_instruction(opcode, &mut self.memory);
+            self.profile_cpu_nanos += start.elapsed().as_nanos() as u64;
+            cycles
+        } else {
+            self.cpu.execute_instruction(opcode, &mut self.memory)
+        };
+
+        // A CPU fica parada enquanto um DMA disparado pela instrução roda
+        let mut cycles = instruction_cycles as u32 + self.memory.take_dma_stall();
+
+        let was_vblank = self.ppu.borrow().vblank;
 
         let mut nmi_triggered = false;
-        for _ in 0..(cycles * 4) {
-            if self.ppu.borrow_mut().step(&mut self.memory) {
-                nmi_triggered = true;
+        let mut pending = cycles;
+        while pending > 0 {
+            // InstructionEnd aplica todos os dots de uma vez; PerCycle avança ciclo a ciclo
+            let chunk = match self.timing_mode {
+                TimingMode::InstructionEnd => pending,
+                TimingMode::PerCycle => 1,
+            };
+            pending -= chunk;
+
+            for _ in 0..self.cycles_to_dots(chunk, access_cycles) {
+                let line = self.ppu.borrow().scanline;
+                let was_complete = self.ppu.borrow().frame_complete;
+                if self.ppu.borrow_mut().step(&mut self.memory) {
+                    nmi_triggered = true;
+                }
+
+                if !was_complete
+                    && self.ppu.borrow().frame_complete
+                    && let Some(callback) = self.frame_callback.as_mut()
+                {
+                    callback(&self.ppu.borrow().framebuffer);
+                }
+
+                // Refresh da WRAM: a CPU perde alguns ciclos em toda scanline
+                if self.ppu.borrow().scanline != line {
+                    cycles += Self::REFRESH_STALL_CYCLES;
+                    pending += Self::REFRESH_STALL_CYCLES;
+                }
             }
         }
 
-        if nmi_triggered && self.ppu.borrow().nmi_enabled && !self.cpu.get_flag(Cpu::FLAG_IRQ) {
+        self.cpu.cycles += cycles as u64;
+
+        #[cfg(feature = "apu")]
+        self.memory.apu.catch_up(self.cpu.cycles);
+
+        if self.profiling && !was_vblank && self.ppu.borrow().vblank {
+            self.finish_frame_profile();
+        }
+
+        // NMI não é mascarado pela flag I
+        if nmi_triggered && self.ppu.borrow().nmi_enabled {
             self.cpu.handle_nmi(&mut self.memory);
         }
 
         cycles
     }
 
+    // Executa instruções até gastar pelo menos `cycles` ciclos; retorna os ciclos gastos
+    pub fn run_cycles(&mut self, cycles: u64) -> u64 {
+        let start = self.cpu.cycles;
+        while self.cpu.cycles - start < cycles {
+            self.step();
+        }
+        self.cpu.cycles - start
+    }
+
+    // Notifica o host a cada frame completo (início do VBlank), sem precisar de polling
+    pub fn set_frame_callback(&mut self, callback: FrameCallback) {
+        self.frame_callback = Some(callback);
+    }
+
+    pub fn clear_frame_callback(&mut self) {
+        self.frame_callback = None;
+    }
+
+    pub fn set_timing_mode(&mut self, mode: TimingMode) {
+        self.timing_mode = mode;
+        self.master_remainder = 0;
+    }
+
+    pub fn timing_mode(&self) -> TimingMode {
+        self.timing_mode
+    }
+
+    fn cycles_to_dots(&mut self, cycles: u32, access_cycles: u32) -> u32 {
+        match self.timing_mode {
+            TimingMode::InstructionEnd => cycles * 4,
+            TimingMode::PerCycle => {
+                let master = cycles * access_cycles + self.master_remainder;
+                self.master_remainder = master % 4;
+                master / 4
+            }
+        }
+    }
+
+    // Repetições do mesmo PC que contam como loop infinito (0 desliga)
+    pub fn set_loop_threshold(&mut self, threshold: u32) {
+        self.loop_threshold = threshold;
+    }
+
+    // Ciclos de CPU que run_until/run_frame podem gastar antes de desistir (0 desliga)
+    pub fn set_cycle_budget(&mut self, cycles: u64) {
+        self.cycle_budget = cycles;
+    }
+
+    // Timeout se a chamada iniciada em start_cycles já passou do orçamento
+    fn budget_exceeded(&self, start_cycles: u64) -> Option<RunStop> {
+        let cycles = self.cpu.cycles.saturating_sub(start_cycles);
+        if self.cycle_budget > 0 && cycles >= self.cycle_budget {
+            Some(RunStop::Timeout { cycles })
+        } else {
+            None
+        }
+    }
+
+    // Executa instruções até a condição ser verdadeira, o PC ficar preso ou o orçamento acabar
+    pub fn run_until<F: FnMut(&System) -> bool>(&mut self, mut condition: F) -> RunStop {
+        let start_cycles = self.cpu.cycles;
+        let mut last_pc = self.cpu.full_pc();
+        let mut count = 0;
+
+        loop {
+            self.step();
+
+            if condition(self) {
+                return RunStop::Condition;
+            }
+
+            if let Some(timeout) = self.budget_exceeded(start_cycles) {
+                return timeout;
+            }
+
+            if self.cpu.full_pc() == last_pc {
+                count += 1;
+                if self.loop_threshold > 0 && count >= self.loop_threshold {
+                    return RunStop::InfiniteLoop { pc: last_pc, count };
+                }
+            } else {
+                count = 0;
+                last_pc = self.cpu.full_pc();
+            }
+        }
+    }
+
+    // Carrega o estado inicial de um teste single-step, roda pelo número de ciclos dado
+    // e devolve o estado final (a RAM é relida nos mesmos endereços do estado inicial)
+    pub fn run_single_test(&mut self, initial: &CpuTestState, cycles: u64) -> CpuTestState {
+        for &(addr, value) in &initial.ram {
+            self.memory.write(addr, value);
+        }
+
+        self.cpu.a = initial.a;
+        self.cpu.x = initial.x;
+        self.cpu.y = initial.y;
+        self.cpu.sp = initial.sp;
+        self.cpu.dp = initial.dp;
+        self.cpu.db = initial.db;
+        self.cpu.pb = initial.pb;
+        self.cpu.pc = initial.pc;
+        self.cpu.p = initial.p;
+        self.cpu.e_flag = initial.e;
+        self.cpu.m_flag = initial.e || (initial.p & 0x20) != 0;
+        self.cpu.x_flag = initial.e || (initial.p & 0x10) != 0;
+
+        let start = self.cpu.cycles;
+        while self.cpu.cycles - start < cycles {
+            self.step();
+        }
+
+        CpuTestState {
+            pc: self.cpu.pc,
+            sp: self.cpu.sp,
+            a: self.cpu.a,
+            x: self.cpu.x,
+            y: self.cpu.y,
+            db: self.cpu.db,
+            dp: self.cpu.dp,
+            pb: self.cpu.pb,
+            p: self.cpu.p,
+            e: self.cpu.e_flag,
+            ram: initial.ram.iter().map(|&(addr, _)| (addr, self.memory.peek(addr))).collect(),
+        }
+    }
+
+    // Snapshot da CPU, das memórias, da PPU e do I/O, no formato de crate::state
+    pub fn save_state(&self) -> Vec<u8> {
+        let cpu = &self.cpu;
+        let mut registers = Vec::new();
+        for word in [cpu.a, cpu.x, cpu.y, cpu.sp, cpu.dp] {
+            registers.extend_from_slice(&word.to_le_bytes());
+        }
+        registers.extend_from_slice(&cpu.full_pc().to_le_bytes());
+        registers.extend_from_slice(&[cpu.db, cpu.pb, cpu.p]);
+        registers.extend_from_slice(&[cpu.m_flag as u8, cpu.x_flag as u8, cpu.e_flag as u8]);
+        registers.extend_from_slice(&cpu.cycles.to_le_bytes());
+
+        let mut out = Vec::new();
+        state::write_header(&mut out);
+        state::write_section(&mut out, state::SECTION_CPU, &registers);
+        state::write_section(&mut out, state::SECTION_WRAM, &self.memory.wram);
+        state::write_section(&mut out, state::SECTION_SRAM, &self.memory.sram);
+        state::write_section(&mut out, state::SECTION_VRAM, &self.memory.vram);
+        state::write_section(&mut out, state::SECTION_CGRAM, &self.memory.cgram);
+        state::write_section(&mut out, state::SECTION_OAM, &self.memory.oam);
+        state::write_section(&mut out, state::SECTION_PPU, &self.ppu_state());
+        state::write_section(&mut out, state::SECTION_IO, &self.io_state());
+
+        #[cfg(feature = "apu")]
+        state::write_section(&mut out, state::SECTION_APU, &self.apu_state());
+
+        out
+    }
+
+    #[cfg(feature = "apu")]
+    fn apu_state(&self) -> Vec<u8> {
+        let mut apu = Vec::new();
+        self.memory.apu.save_state(&mut apu);
+        apu
+    }
+
+    fn ppu_state(&self) -> Vec<u8> {
+        let mut ppu = Vec::new();
+        self.ppu.borrow().save_state(&mut ppu);
+        ppu
+    }
+
+    // Dots ainda não fechados entram junto com o I/O para o feixe voltar ao mesmo ponto
+    fn io_state(&self) -> Vec<u8> {
+        let mut io = self.master_remainder.to_le_bytes().to_vec();
+        self.memory.save_io(&mut io);
+        io
+    }
+
+    // Restaura um snapshot de save_state; em caso de erro o sistema não é alterado
+    pub fn load_state(&mut self, data: &[u8]) -> std::io::Result<()> {
+        let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "save state inválido");
+
+        let sections = state::sections(data).ok_or_else(invalid)?;
+        let registers = state::section(&sections, state::SECTION_CPU).ok_or_else(invalid)?;
+        let wram = state::section(&sections, state::SECTION_WRAM).ok_or_else(invalid)?;
+        let sram = state::section(&sections, state::SECTION_SRAM).ok_or_else(invalid)?;
+        let vram = state::section(&sections, state::SECTION_VRAM).ok_or_else(invalid)?;
+        let cgram = state::section(&sections, state::SECTION_CGRAM).ok_or_else(invalid)?;
+        let oam = state::section(&sections, state::SECTION_OAM).ok_or_else(invalid)?;
+        let ppu = state::section(&sections, state::SECTION_PPU).ok_or_else(invalid)?;
+        let io = state::section(&sections, state::SECTION_IO).ok_or_else(invalid)?;
+
+        if registers.len() != 28 || wram.len() != self.memory.wram.len() || vram.len() != self.memory.vram.len()
+            || cgram.len() != self.memory.cgram.len() || oam.len() != self.memory.oam.len()
+            || ppu.len() != self.ppu_state().len()
+        {
+            return Err(invalid());
+        }
+
+        #[cfg(feature = "apu")]
+        let apu = state::section(&sections, state::SECTION_APU)
+            .filter(|apu| apu.len() == self.apu_state().len())
+            .ok_or_else(invalid)?;
+
+        // O I/O tem tamanho variável: é o único passo que ainda pode falhar
+        let mut reader = state::Reader::new(io);
+        let master_remainder = reader.u32().ok_or_else(invalid)?;
+        self.memory.load_io(&mut reader).ok_or_else(invalid)?;
+        self.master_remainder = master_remainder;
+
+        self.ppu.borrow_mut().load_state(&mut state::Reader::new(ppu)).ok_or_else(invalid)?;
+
+        #[cfg(feature = "apu")]
+        self.memory.apu.load_state(&mut state::Reader::new(apu)).ok_or_else(invalid)?;
+
+        let word = |i: usize| u16::from_le_bytes([registers[i], registers[i + 1]]);
+        let cpu = &mut self.cpu;
+        cpu.a = word(0);
+        cpu.x = word(2);
+        cpu.y = word(4);
+        cpu.sp = word(6);
+        cpu.dp = word(8);
+        // PB sai do byte alto do PC de 24 bits; o byte 15 é redundante
+        cpu.set_full_pc(u32::from_le_bytes(registers[10..14].try_into().unwrap()));
+        cpu.db = registers[14];
+        cpu.p = registers[16];
+        cpu.m_flag = registers[17] != 0;
+        cpu.x_flag = registers[18] != 0;
+        cpu.e_flag = registers[19] != 0;
+        cpu.cycles = u64::from_le_bytes(registers[20..28].try_into().unwrap());
+
+        self.memory.wram.copy_from_slice(wram);
+        self.memory.vram.copy_from_slice(vram);
+        self.memory.cgram.copy_from_slice(cgram);
+        self.memory.oam.copy_from_slice(oam);
+        self.memory.sram = sram.to_vec();
+        self.memory.sram_size = sram.len();
+        Ok(())
+    }
+
+    // Executa n instruções escrevendo o estado da CPU antes de cada uma (para diff com logs de referência)
+    pub fn trace_to<W: Write>(&mut self, writer: &mut W, instructions: u32) -> std::io::Result<()> {
+        for _ in 0..instructions {
+            writeln!(writer, "{}", self.cpu.state_line())?;
+            self.step();
+        }
+        Ok(())
+    }
+
+    // Roda n frames desenhando só o último; timing, NMI e IRQ seguem normais.
+    // Para no primeiro frame que estourar o orçamento de ciclos
+    pub fn run_frames_fast(&mut self, frames: u32) -> RunStop {
+        let mut stop = RunStop::Condition;
+
+        for frame in 0..frames {
+            self.ppu.borrow_mut().skip_render = frame + 1 < frames;
+            stop = self.run_frame();
+            if stop != RunStop::Condition {
+                break;
+            }
+        }
+
+        self.ppu.borrow_mut().skip_render = false;
+        stop
+    }
+
+    // Roda um frame e escreve o resultado em um buffer do chamador, sem alocar.
+    // O tamanho é conferido antes de rodar; se o frame não fechar (Timeout, loop
+    // infinito) o motivo é devolvido e o buffer fica como estava
+    pub fn run_frame_into(&mut self, buffer: &mut [u32]) -> Result<RunStop, RenderError> {
+        let expected = self.ppu.borrow().framebuffer.len();
+        if buffer.len() != expected {
+            return Err(RenderError::BufferSize { expected, actual: buffer.len() });
+        }
+
+        let stop = self.run_frames_fast(1);
+        if stop == RunStop::Condition {
+            self.ppu.borrow().render_into(buffer)?;
+        }
+        Ok(stop)
+    }
+
+    // Executa até o início do próximo VBlank, que fecha o frame. Loops de espera são
+    // normais aqui, então só o orçamento de ciclos interrompe (Timeout)
+    pub fn run_frame(&mut self) -> RunStop {
+        let start_cycles = self.cpu.cycles;
+
+        loop {
+            let was_vblank = self.is_vblank();
+            self.step();
+            if !was_vblank && self.is_vblank() {
+                return RunStop::Condition;
+            }
+
+            if let Some(timeout) = self.budget_exceeded(start_cycles) {
+                return timeout;
+            }
+        }
+    }
+
+    pub fn set_rom_path(&mut self, rom_path: &str) {
+        self.rom_path = Some(rom_path.to_string());
+    }
+
+    // Save de bateria ao lado da ROM: jogo.smc -> jogo.srm
+    pub fn default_save_path(rom_path: &str) -> String {
+        Path::new(rom_path).with_extension("srm").to_string_lossy().into_owned()
+    }
+
+    // Grava a SRAM no .srm da ROM só se ela mudou; retorna se gravou
+    pub fn autosave_if_dirty(&mut self) -> std::io::Result<bool> {
+        let Some(rom_path) = &self.rom_path else {
+            return Ok(false);
+        };
+
+        if !self.memory.sram_dirty || self.memory.sram_size == 0 {
+            return Ok(false);
+        }
+
+        self.memory.save_sram(&Self::default_save_path(rom_path))?;
+        self.memory.sram_dirty = false;
+        Ok(true)
+    }
+
+    // Liga/desliga a coleta de tempo por frame; desligado não há custo extra
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+        self.profile_cpu_nanos = 0;
+        self.profile_start_cycles = self.cpu.cycles;
+        self.last_profile = None;
+
+        let mut ppu = self.ppu.borrow_mut();
+        ppu.profiling = enabled;
+        ppu.render_nanos = 0;
+    }
+
+    pub fn enable_dma_log(&mut self, enabled: bool) {
+        self.memory.set_dma_log(enabled);
+    }
+
+    pub fn dma_log(&self) -> &[DmaTransfer] {
+        self.memory.dma_log()
+    }
+
+    pub fn get_frame_profile(&self) -> Option<FrameProfile> {
+        self.last_profile
+    }
+
+    fn finish_frame_profile(&mut self) {
+        let mut ppu = self.ppu.borrow_mut();
+
+        self.last_profile = Some(FrameProfile {
+            cpu_nanos: self.profile_cpu_nanos,
+            render_nanos: ppu.render_nanos,
+            cpu_cycles: self.cpu.cycles - self.profile_start_cycles,
+        });
+
+        self.profile_cpu_nanos = 0;
+        self.profile_start_cycles = self.cpu.cycles;
+        ppu.render_nanos = 0;
+    }
+
+    // Controle na porta 1-4: JOYxL/JOYxH só mudam no próximo auto-read,
+    // e a leitura manual ($4016/$4017) no próximo strobe
+    pub fn set_controller(&mut self, port: u8, state: JoypadState) {
+        let Some(index) = (port as usize).checked_sub(1).filter(|&index| index < 4) else {
+            return;
+        };
+
+        self.memory.joypads[index] = state.bits();
+    }
+
     pub fn reset(&mut self) {
         self.cpu.reset();
         self.ppu.borrow_mut().reset();
+        self.ppu.borrow_mut().set_region(self.memory.region);
     }
 
     pub fn frame_ready(&self) -> bool {
@@ -55,11 +576,26 @@ impl System {
         self.ppu.borrow().get_framebuffer().to_vec()
     }
 
-    pub fn get_ppu(&self) -> std::cell::Ref<Ppu> {
+    // Empresta o framebuffer sem copiar (ex.: upload direto para uma textura)
+    pub fn with_framebuffer<R, F: FnOnce(&[u32]) -> R>(&self, f: F) -> R {
+        f(self.ppu.borrow().get_framebuffer())
+    }
+
+    pub fn region(&self) -> Region {
+        self.memory.region
+    }
+
+    // Força a região, ignorando o código de país do header
+    pub fn set_region(&mut self, region: Region) {
+        self.memory.region = region;
+        self.ppu.borrow_mut().set_region(region);
+    }
+
+    pub fn get_ppu(&self) -> std::cell::Ref<'_, Ppu> {
         self.ppu.borrow()
     }
 
-    pub fn get_ppu_mut(&self) -> std::cell::RefMut<Ppu> {
+    pub fn get_ppu_mut(&self) -> std::cell::RefMut<'_, Ppu> {
         self.ppu.borrow_mut()
     }
 
@@ -0,0 +1,115 @@
+#![cfg(feature = "apu")]
+
+use snes_emulator::memory::Memory;
+use snes_emulator::{Ppu, System};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn create_memory() -> Memory {
+    Memory::new(vec![0; 0x10000], Rc::new(RefCell::new(Ppu::new())))
+}
+
+// Upload pelo protocolo do IPL, como um jogo faria pela CPU principal
+fn upload_program(memory: &mut Memory, dest: u16, program: &[u8]) {
+    assert_eq!(memory.read(0x002140), 0xAA);
+    assert_eq!(memory.read(0x002141), 0xBB);
+
+    memory.write(0x002141, 0x01);
+    memory.write(0x002142, dest as u8);
+    memory.write(0x002143, (dest >> 8) as u8);
+    memory.write(0x002140, 0xCC);
+    assert_eq!(memory.read(0x002140), 0xCC);
+
+    for (index, &byte) in program.iter().enumerate() {
+        memory.write(0x002141, byte);
+        memory.write(0x002140, index as u8);
+        assert_eq!(memory.read(0x002140), index as u8);
+    }
+
+    // Porta 1 zerada: salta para o endereço de execução
+    memory.write(0x002141, 0x00);
+    memory.write(0x002142, dest as u8);
+    memory.write(0x002143, (dest >> 8) as u8);
+    memory.write(0x002140, (program.len() as u8).wrapping_add(1));
+}
+
+#[test]
+fn test_spc700_runs_uploaded_program() {
+    let mut memory = create_memory();
+
+    let program = [
+        0xE8, 0x5A, // MOV A,#$5A
+        0xC4, 0xF5, // MOV $F5,A
+        0xE4, 0xF4, // loop: MOV A,$F4
+        0xBC,       // INC A
+        0xC4, 0xF6, // MOV $F6,A
+        0x2F, 0xF9, // BRA loop
+    ];
+
+    upload_program(&mut memory, 0x0200, &program);
+    assert_eq!(memory.apu.ram[0x0200..0x0200 + program.len()], program);
+    assert_eq!(memory.apu.pc, 0x0200);
+
+    memory.apu.run(100);
+    assert_eq!(memory.read(0x002141), 0x5A);
+
+    // O programa devolve porta 0 + 1 na porta 2
+    memory.write(0x002140, 0x41);
+    memory.apu.run(100);
+    assert_eq!(memory.read(0x002142), 0x42);
+}
+
+#[test]
+fn test_apu_ports_mirrored() {
+    let mut memory = create_memory();
+    assert_eq!(memory.read(0x002154), 0xAA);
+
+    // Kick do IPL pelos espelhos: o eco aparece na porta 0 real
+    memory.write(0x002155, 0x01);
+    memory.write(0x002150, 0xCC);
+    assert_eq!(memory.apu.cpu_to_apu[0], 0xCC);
+    assert_eq!(memory.read(0x002140), 0xCC);
+    assert_eq!(memory.read(0x00217C), 0xCC);
+}
+
+#[test]
+fn test_save_state_includes_apu() {
+    let mut system = System::new(vec![0; 0x10000]);
+    system.memory.write(0x002141, 0x01);
+    system.memory.write(0x002142, 0x00);
+    system.memory.write(0x002143, 0x03);
+    system.memory.write(0x002140, 0xCC); // Início de bloco do IPL em $0300
+    system.memory.apu.ram[0x0400] = 0x5A;
+    let cycles = system.memory.apu.cycles;
+
+    let state = system.save_state();
+
+    system.memory.apu.ram[0x0400] = 0x00;
+    system.memory.write(0x002141, 0x77);
+    system.memory.write(0x002140, 0x00); // Primeiro byte do bloco
+    assert_eq!(system.memory.apu.ram[0x0300], 0x77);
+
+    system.load_state(&state).unwrap();
+    assert_eq!(system.memory.apu.ram[0x0400], 0x5A);
+    assert_eq!(system.memory.apu.ram[0x0300], 0x00);
+    assert_eq!(system.memory.apu.cycles, cycles);
+    assert_eq!(system.memory.read(0x002140), 0xCC);
+}
+
+#[test]
+fn test_spc700_records_unknown_opcode() {
+    let mut memory = create_memory();
+    upload_program(&mut memory, 0x0200, &[
+        0x00, // NOP
+        0xCF, // MUL YA (não implementado)
+    ]);
+
+    memory.apu.run(4);
+    assert_eq!(memory.apu.unknown_opcode, Some((0xCF, 0x0201)));
+    assert!(memory.apu.halted);
+
+    // Parado: o PC não passa do opcode desconhecido
+    let pc = memory.apu.pc;
+    memory.apu.run(16);
+    assert_eq!(memory.apu.pc, pc);
+}
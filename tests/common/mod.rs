@@ -0,0 +1,16 @@
+// ROM LoROM de 64KB usada pelos testes de integração
+
+// NOPs com `program` a partir de $00:8000 e `title` no header em $7FC0
+pub fn build_rom(program: &[u8], title: &str) -> Vec<u8> {
+    let mut rom = vec![0xEA; 0x10000]; // NOPs
+    rom[..program.len()].copy_from_slice(program);
+
+    // Título com 21 bytes, completado com espaços
+    let mut name = [b' '; 21];
+    name[..title.len()].copy_from_slice(title.as_bytes());
+
+    let header_start = 0x7FC0;
+    rom[header_start..header_start + 21].copy_from_slice(&name);
+
+    rom
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use snes_emulator::{Debugger, System};
+
+fn create_test_rom(program: &[u8]) -> Vec<u8> {
+    common::build_rom(program, "DEBUG TEST")
+}
+
+#[test]
+fn test_debugger_step_and_inspect() {
+    let rom = create_test_rom(&[
+        0xA9, 0x42,       // LDA #$42
+        0x85, 0x10,       // STA $10
+        0xA2, 0x07,       // LDX #$07
+        0x4C, 0x06, 0x80, // JMP $8006
+    ]);
+    let mut debugger = Debugger::new(System::new(rom));
+
+    debugger.step_instruction();
+    assert_eq!(debugger.regs().a & 0xFF, 0x42);
+    assert_eq!(debugger.regs().pc, 0x8002);
+    assert_eq!(debugger.read_mem(0x7E0010), 0x00);
+
+    debugger.step_instruction();
+    debugger.step_instruction();
+    assert_eq!(debugger.read_mem(0x7E0010), 0x42);
+    assert_eq!(debugger.regs().x, 0x07);
+
+    debugger.write_mem(0x7E0011, 0x99);
+    assert_eq!(debugger.read_mem(0x000011), 0x99);
+
+    let line = debugger.system.get_scanline();
+    assert!(debugger.step_scanline() > 0);
+    assert_eq!(debugger.system.get_scanline(), line + 1);
+
+    debugger.step_frame();
+    assert!(debugger.system.is_vblank());
+    assert_eq!(debugger.regs().pc, 0x8006);
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use snes_emulator::{Button, Emulator};
+
+fn create_test_rom() -> Vec<u8> {
+    // $8000: código que não deve rodar
+    let mut rom = common::build_rom(&[
+        0xA9, 0x99,       // LDA #$99
+        0x85, 0x10,       // STA $10
+        0xDB,             // STP
+    ], "EMULATOR TEST");
+
+    // $8100: entrada pelo vetor de reset
+    rom[0x100..0x112].copy_from_slice(&[
+        0xA9, 0x01,       // LDA #$01
+        0x8D, 0x00, 0x42, // STA $4200 (auto-read)
+        0xA9, 0x42,       // LDA #$42
+        0x85, 0x10,       // STA $10
+        0xAD, 0x19, 0x42, // LDA $4219
+        0x85, 0x11,       // STA $11
+        0x4C, 0x09, 0x81, // JMP $8109
+        0xEA,
+    ]);
+
+    rom[0x7FFC] = 0x00; // Vetor de reset: $8100
+    rom[0x7FFD] = 0x81;
+
+    rom
+}
+
+#[test]
+fn test_emulator_honors_reset_vector_and_runs_frame() {
+    let mut emulator = Emulator::load(create_test_rom());
+    assert_eq!(emulator.system.cpu.pc, 0x008100);
+
+    emulator.set_button(Button::Start, true);
+    let frame_len = emulator.run_frame().len();
+    assert_eq!(frame_len, 256 * 224);
+    assert!(emulator.system.is_vblank());
+    assert_eq!(emulator.system.memory.read(0x000010), 0x42);
+
+    // O auto-read latcheia no VBlank; a ROM só lê o JOY1 no frame seguinte
+    emulator.run_frame();
+    assert_eq!(emulator.system.memory.read(0x000011), 0x10); // Start: bit 12 de JOY1
+
+    emulator.set_button(Button::Start, false);
+    emulator.run_frame();
+    emulator.run_frame();
+    assert_eq!(emulator.system.memory.read(0x000011), 0x00);
+}
+
+#[test]
+fn test_emulator_save_and_load_state() {
+    let mut emulator = Emulator::load(create_test_rom());
+    emulator.run_frame();
+
+    let state = emulator.save_state();
+    let pc = emulator.system.cpu.pc;
+    let cycles = emulator.system.cpu.cycles;
+
+    emulator.system.memory.write(0x000010, 0x00);
+    emulator.run_frame();
+
+    emulator.load_state(&state).unwrap();
+    assert_eq!(emulator.system.memory.read(0x000010), 0x42);
+    assert_eq!(emulator.system.cpu.pc, pc);
+    assert_eq!(emulator.system.cpu.cycles, cycles);
+
+    assert!(emulator.load_state(b"lixo").is_err());
+}
+
+#[test]
+fn test_emulator_load_state_restores_video_state() {
+    let mut emulator = Emulator::load(create_test_rom());
+    emulator.system.memory.write_cgram(0, 0x1F);
+    emulator.system.memory.write_oam(0, 0x80);
+    emulator.run_frame();
+
+    let state = emulator.save_state();
+    let scanline = emulator.system.get_ppu().scanline;
+
+    emulator.system.memory.write_cgram(0, 0x00);
+    emulator.system.memory.write_oam(0, 0x00);
+    emulator.system.run_cycles(341 * 20 / 4);
+    assert_ne!(emulator.system.get_ppu().scanline, scanline);
+
+    emulator.load_state(&state).unwrap();
+    assert_eq!(emulator.system.memory.read_cgram(0), 0x1F);
+    assert_eq!(emulator.system.memory.read_oam(0), 0x80);
+    assert_eq!(emulator.system.get_ppu().scanline, scanline);
+}
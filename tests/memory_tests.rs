@@ -1,4 +1,11 @@
-use snes_emulator::memory::{Memory, RomType};
+use snes_emulator::memory::{IoHandler, MemError, Memory, RomType, SearchRegion};
+use snes_emulator::Ppu;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn create_memory(rom: Vec<u8>) -> Memory {
+    Memory::new(rom, Rc::new(RefCell::new(Ppu::new())))
+}
 
 fn create_test_rom() -> Vec<u8> {
     let mut rom = vec![0; 0x10000]; // 64KB ROM
@@ -25,7 +32,7 @@ fn create_test_rom() -> Vec<u8> {
 #[test]
 fn test_memory_creation() {
     let rom = create_test_rom();
-    let memory = Memory::new(rom);
+    let memory = create_memory(rom);
     
     assert_eq!(memory.sram_size, 0x8000); // 32KB
     assert!(matches!(memory.rom_type, RomType::LoRom));
@@ -36,7 +43,7 @@ fn test_memory_creation() {
 #[test]
 fn test_wram_read_write() {
     let rom = create_test_rom();
-    let mut memory = Memory::new(rom);
+    let mut memory = create_memory(rom);
     
     // Teste WRAM mirror (banco 0)
     memory.write(0x001000, 0xAB);
@@ -63,7 +70,7 @@ fn test_rom_read() {
     rom[0x8000] = 0x56; // Mapeia para $01:8000
     rom[0x8001] = 0x78; // Mapeia para $01:8001
     
-    let memory = Memory::new(rom);
+    let memory = create_memory(rom);
     
     // Teste leitura ROM banco 0
     assert_eq!(memory.read(0x008000), 0x12);
@@ -81,7 +88,7 @@ fn test_rom_read() {
 #[test]
 fn test_rom_write_readonly() {
     let rom = create_test_rom();
-    let mut memory = Memory::new(rom);
+    let mut memory = create_memory(rom);
     
     // ROM deve ser read-only
     memory.write(0x008000, 0xFF);
@@ -91,7 +98,7 @@ fn test_rom_write_readonly() {
 #[test]
 fn test_sram_read_write() {
     let rom = create_test_rom();
-    let mut memory = Memory::new(rom);
+    let mut memory = create_memory(rom);
     
     // Teste SRAM
     memory.write(0x006000, 0x55);
@@ -104,41 +111,99 @@ fn test_sram_read_write() {
 #[test]
 fn test_vram_access() {
     let rom = create_test_rom();
-    let mut memory = Memory::new(rom);
+    let mut memory = create_memory(rom);
     
     // Teste acesso direto à VRAM
     memory.write_vram(0x1000, 0x42);
     assert_eq!(memory.read_vram(0x1000), 0x42);
     
     // Teste acesso via registradores PPU
+    memory.write(0x002115, 0x80); // VMAIN: incrementa após o byte alto
     memory.write(0x002116, 0x00); // VRAM addr low
-    memory.write(0x002117, 0x10); // VRAM addr high = 0x1000
-    memory.write(0x002118, 0x33); // VRAM data write
-    
-    assert_eq!(memory.read_vram(0x1000), 0x33);
+    memory.write(0x002117, 0x10); // VRAM addr high = word 0x1000
+    memory.write(0x002118, 0x33); // VRAM data write (byte baixo)
+    memory.write(0x002119, 0x44); // VRAM data write (byte alto)
+    
+    // Endereço em words: word 0x1000 = bytes 0x2000/0x2001
+    assert_eq!(memory.read_vram(0x2000), 0x33);
+    assert_eq!(memory.read_vram(0x2001), 0x44);
+    assert_eq!(memory.vram_word(0x1000), 0x4433);
+}
+
+#[test]
+fn test_wram_port_wraps_at_128k() {
+    let mut memory = create_memory(create_test_rom());
+
+    // $2183 só usa o bit 0: $FF seleciona a metade alta
+    memory.write(0x002181, 0xFF);
+    memory.write(0x002182, 0xFF);
+    memory.write(0x002183, 0xFF);
+    memory.write(0x002180, 0xAA); // $1FFFF
+    memory.write(0x002180, 0xBB); // Wrap para $00000
+
+    assert_eq!(memory.wram[0x1FFFF], 0xAA);
+    assert_eq!(memory.wram[0x00000], 0xBB);
+    assert_eq!(memory.read(0x7FFFFF), 0xAA);
+
+    // Leituras também avançam o endereço
+    memory.write(0x002181, 0xFF);
+    memory.write(0x002182, 0xFF);
+    memory.write(0x002183, 0x01);
+    assert_eq!(memory.read(0x002180), 0xAA);
+    assert_eq!(memory.read(0x002180), 0xBB);
+}
+
+#[test]
+fn test_vram_address_write_prefetches_read_buffer() {
+    let mut memory = create_memory(create_test_rom());
+    memory.write_vram(0x2468, 0xCD);
+    memory.write_vram(0x2469, 0xAB);
+    memory.write_vram(0x246A, 0x11);
+    memory.write_vram(0x246B, 0x22);
+
+    memory.write(0x002115, 0x80); // Incrementa após ler $213A
+    memory.write(0x002116, 0x34);
+    memory.write(0x002117, 0x12); // Word $1234
+
+    assert_eq!(memory.read(0x002139), 0xCD);
+    assert_eq!(memory.read(0x00213A), 0xAB);
+
+    // Trocar só o byte baixo também recarrega o buffer
+    memory.write(0x002116, 0x35);
+    assert_eq!(memory.read(0x002139), 0x11);
+    assert_eq!(memory.read(0x00213A), 0x22);
 }
 
 #[test]
 fn test_oam_access() {
     let rom = create_test_rom();
-    let mut memory = Memory::new(rom);
+    let mut memory = create_memory(rom);
     
     // Teste acesso direto ao OAM
     memory.write_oam(0x100, 0x77);
     assert_eq!(memory.read_oam(0x100), 0x77);
     
     // Teste acesso via registradores PPU
-    memory.write(0x002102, 0x00); // OAM addr low
-    memory.write(0x002103, 0x01); // OAM addr high = 0x100
-    memory.write(0x002104, 0x88); // OAM data write
+    memory.write(0x002102, 0x80); // OAM addr low (word $080 = byte $100)
+    memory.write(0x002103, 0x00); // OAM addr high
+    memory.write(0x002104, 0x88); // OAM data write (fica no latch)
+    assert_eq!(memory.read_oam(0x100), 0x77);
+    memory.write(0x002104, 0x99); // Segundo byte grava o par
     
     assert_eq!(memory.read_oam(0x100), 0x88);
+    assert_eq!(memory.read_oam(0x101), 0x99);
+
+    // Bit 0 de $2103 é o bit 8 do endereço de word: word $100 = tabela alta
+    memory.write(0x002102, 0x00);
+    memory.write(0x002103, 0x01);
+    memory.write(0x002104, 0x66);
+    assert_eq!(memory.read_oam(0x200), 0x66);
 }
 
 #[test]
 fn test_cgram_access() {
     let rom = create_test_rom();
-    let mut memory = Memory::new(rom);
+    let mut memory = create_memory(rom);
     
     // Teste acesso direto ao CGRAM
     memory.write_cgram(0x50, 0x99);
@@ -154,7 +219,7 @@ fn test_cgram_access() {
 #[test]
 fn test_rom_title() {
     let rom = create_test_rom();
-    let memory = Memory::new(rom);
+    let memory = create_memory(rom);
     
     let title = memory.get_rom_title();
     assert_eq!(title, "TEST ROM");
@@ -166,22 +231,22 @@ fn test_sram_size_detection() {
     
     // Teste diferentes tamanhos de SRAM
     rom[0x7FD8] = 0x01; // 2KB
-    let memory = Memory::new(rom.clone());
+    let memory = create_memory(rom.clone());
     assert_eq!(memory.sram_size, 0x800);
     
     rom[0x7FD8] = 0x02; // 8KB
-    let memory = Memory::new(rom.clone());
+    let memory = create_memory(rom.clone());
     assert_eq!(memory.sram_size, 0x2000);
     
     rom[0x7FD8] = 0x03; // 32KB
-    let memory = Memory::new(rom.clone());
+    let memory = create_memory(rom.clone());
     assert_eq!(memory.sram_size, 0x8000);
 }
 
 #[test]
 fn test_bounds_checking() {
     let rom = create_test_rom();
-    let mut memory = Memory::new(rom);
+    let mut memory = create_memory(rom);
     
     // Teste leitura além dos limites - deve retornar 0
     assert_eq!(memory.read(0xFF0000), 0); // Banco não mapeado
@@ -193,4 +258,222 @@ fn test_bounds_checking() {
     memory.write_vram(0xFFFF, 0xFF);
     memory.write_oam(0x300, 0xFF);
     memory.write_cgram(0x300, 0xFF);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_dump_region() {
+    let rom = create_test_rom();
+    let mut memory = create_memory(rom);
+
+    let pattern: Vec<u8> = (0..16).map(|i| 0xA0 ^ (i as u8 * 7)).collect();
+    for (i, &byte) in pattern.iter().enumerate() {
+        memory.write(0x7E2000 + i as u32, byte);
+    }
+
+    assert_eq!(memory.dump_region(0x7E2000, 16), pattern);
+
+    // Mirror no banco 00 deve ver os mesmos bytes
+    assert_eq!(memory.dump_region(0x002000, 16), pattern);
+    assert!(memory.dump_region(0x7E2000, 0).is_empty());
+}
+
+#[test]
+fn test_force_rom_type() {
+    let mut rom = create_test_rom();
+    rom.resize(0x20000, 0);
+    rom[0x0000] = 0x11;  // LoROM $00:8000
+    rom[0x8000] = 0x22;  // HiROM $00:8000
+    rom[0x18000] = 0x33; // HiROM $C1:8000
+
+    let mut memory = create_memory(rom);
+    assert_eq!(memory.rom_type, RomType::LoRom);
+    assert_eq!(memory.read(0x008000), 0x11);
+    assert_eq!(memory.read(0xC18000), 0x11); // Não mapeado em LoROM: open bus
+
+    memory.set_rom_type(RomType::HiRom);
+    assert_eq!(memory.rom_type, RomType::HiRom);
+    assert_eq!(memory.read(0x008000), 0x22);
+    assert_eq!(memory.read(0x808000), 0x22);
+    assert_eq!(memory.read(0xC18000), 0x33);
+    assert_eq!(memory.read(0x418000), 0x33);
+
+    // Header HiROM ($FFD8) zerado: sem SRAM
+    assert_eq!(memory.sram_size, 0);
+}
+
+#[test]
+fn test_oam_write_latch_pairs() {
+    let ppu = Rc::new(RefCell::new(Ppu::new()));
+    let mut memory = Memory::new(create_test_rom(), Rc::clone(&ppu));
+
+    memory.write(0x002102, 0x00);
+    memory.write(0x002103, 0x00);
+
+    // Só pares completos chegam à tabela baixa
+    for value in [0x10, 0x20, 0x30, 0x40, 0x50] {
+        memory.write(0x002104, value);
+    }
+    assert_eq!(memory.read_oam(0), 0x10);
+    assert_eq!(memory.read_oam(1), 0x20);
+    assert_eq!(memory.read_oam(2), 0x30);
+    assert_eq!(memory.read_oam(3), 0x40);
+    assert_eq!(memory.read_oam(4), 0x00); // 0x50 ainda no latch
+    assert_eq!(ppu.borrow().oam_addr, 5);
+
+    memory.write(0x002104, 0x60);
+    assert_eq!(memory.read_oam(4), 0x50);
+    assert_eq!(memory.read_oam(5), 0x60);
+
+    // Tabela alta é gravada byte a byte
+    memory.write(0x002102, 0x00);
+    memory.write(0x002103, 0x01);
+    memory.write(0x002104, 0xAA);
+    assert_eq!(memory.read_oam(0x200), 0xAA);
+    assert_eq!(ppu.borrow().oam_addr, 0x201);
+
+    // Bit 7 de $2103 é só a rotação de prioridade, não endereço
+    memory.write(0x002102, 0x02);
+    memory.write(0x002103, 0x80);
+    assert_eq!(ppu.borrow().oam_addr, 0x004);
+    assert!(ppu.borrow().oam_priority);
+
+    memory.write(0x002103, 0x02);
+    assert_eq!(ppu.borrow().oam_addr, 0x004); // Bits 1-6 são ignorados
+    assert!(!ppu.borrow().oam_priority);
+
+    // $2138 segue o mesmo endereço: tabela alta espelhada e wrap em $3FF
+    memory.write(0x002102, 0xFF);
+    memory.write(0x002103, 0x01); // Word $1FF = byte $3FE
+    memory.write_oam(0x21E, 0x11);
+    memory.write_oam(0x21F, 0x22);
+    assert_eq!(memory.read(0x002138), 0x11);
+    assert_eq!(memory.read(0x002138), 0x22);
+    assert_eq!(ppu.borrow().oam_addr, 0x000);
+    assert_eq!(memory.read(0x002138), memory.read_oam(0));
+    assert_eq!(ppu.borrow().oam_addr, 0x001);
+}
+
+#[test]
+fn test_search_wram_for_u16() {
+    let rom = create_test_rom();
+    let mut memory = create_memory(rom);
+
+    // $BEEF em little-endian no endereço $7E:1234
+    memory.write(0x7E1234, 0xEF);
+    memory.write(0x7E1235, 0xBE);
+
+    assert_eq!(memory.search_u16(0xBEEF, SearchRegion::Wram), vec![0x7E1234]);
+    assert_eq!(memory.search_u8(0xBE, SearchRegion::Wram), vec![0x7E1235]);
+    assert!(memory.search(&[], SearchRegion::Wram).is_empty());
+
+    // Título do header na ROM
+    assert_eq!(memory.search(b"TEST ROM", SearchRegion::Rom), vec![0x7FC0]);
+}
+
+#[test]
+fn test_write_only_ppu_registers_read_open_bus() {
+    let rom = create_test_rom();
+    let mut memory = create_memory(rom);
+
+    // Leitura do CGRAM ($213B) deixa $5A no open bus
+    memory.write_cgram(0, 0x5A);
+    memory.write(0x002121, 0x00);
+    assert_eq!(memory.read(0x00213B), 0x5A);
+
+    memory.write(0x002105, 0x01);
+    assert_eq!(memory.read(0x002105), 0x5A);
+    assert_eq!(memory.read(0x002116), 0x5A);
+    assert_eq!(memory.read(0x002100), 0x5A);
+
+    // O valor é o do último acesso ao barramento, não o latch da PPU
+    assert_eq!(memory.read(0x00FFC0), b'T'); // Título no header
+    assert_eq!(memory.read(0x002105), b'T');
+    assert_eq!(memory.read(0x002134), b'T');
+}
+
+#[cfg(not(feature = "apu"))]
+#[test]
+fn test_apu_ports_mirrored() {
+    let rom = create_test_rom();
+    let mut memory = create_memory(rom);
+
+    memory.write(0x002150, 0x5A); // Espelho da porta 0
+    assert_eq!(memory.read(0x002140), 0x5A);
+
+    memory.write(0x002143, 0x77);
+    assert_eq!(memory.read(0x00217F), 0x77);
+}
+
+#[test]
+fn test_memsel_fast_rom_access_cycles() {
+    let rom = create_test_rom();
+    let mut memory = create_memory(rom);
+
+    // MEMSEL=0: ROM sempre lenta
+    assert_eq!(memory.access_cycles(0x808000), 8);
+    assert_eq!(memory.access_cycles(0xC00000), 8);
+
+    memory.write(0x00420D, 0x01);
+    assert!(memory.fast_rom);
+    assert_eq!(memory.access_cycles(0x808000), 6);
+    assert_eq!(memory.access_cycles(0xC00000), 6);
+
+    // Bancos $00-$7F continuam lentos; registradores não dependem do MEMSEL
+    assert_eq!(memory.access_cycles(0x008000), 8);
+    assert_eq!(memory.access_cycles(0x002100), 6);
+    assert_eq!(memory.access_cycles(0x004016), 12);
+
+    memory.write(0x00420D, 0x00);
+    assert_eq!(memory.access_cycles(0x808000), 8);
+}
+
+// Porta de console de debug: guarda os bytes escritos e responde com um valor fixo
+struct DebugConsole {
+    output: Rc<RefCell<Vec<u8>>>,
+}
+
+impl IoHandler for DebugConsole {
+    fn read(&mut self, _addr: u32) -> u8 {
+        0x42
+    }
+
+    fn write(&mut self, _addr: u32, value: u8) {
+        self.output.borrow_mut().push(value);
+    }
+}
+
+#[test]
+fn test_map_io_intercepts_range() {
+    let rom = create_test_rom();
+    let mut memory = create_memory(rom);
+    let output = Rc::new(RefCell::new(Vec::new()));
+
+    memory.map_io(0x002184..0x002185, Box::new(DebugConsole { output: Rc::clone(&output) }));
+
+    memory.write(0x002184, b'H');
+    memory.write(0x002184, b'i');
+    assert_eq!(*output.borrow(), b"Hi");
+    assert_eq!(memory.read(0x002184), 0x42);
+
+    // Fora do intervalo a decodificação normal continua valendo
+    memory.write(0x000010, 0x99);
+    assert_eq!(memory.read(0x000010), 0x99);
+    assert_eq!(output.borrow().len(), 2);
+}
+
+#[test]
+fn test_try_read_distinguishes_unmapped() {
+    let rom = create_test_rom();
+    let mut memory = create_memory(rom);
+
+    memory.write(0x7E0010, 0x5A);
+    assert_eq!(memory.try_read(0x7E0010), Ok(0x5A));
+    assert_eq!(memory.try_read(0x008000), Ok(memory.read(0x008000)));
+
+    // LoROM: bancos $70-$7D e $C0-$FF não têm nada por trás
+    assert_eq!(memory.try_read(0x700000), Err(MemError::Unmapped(0x700000)));
+    assert_eq!(memory.try_read(0xC00000), Err(MemError::Unmapped(0xC00000)));
+
+    // ROM de 64KB: o banco $02 já passa do fim
+    assert_eq!(memory.try_read(0x028000), Err(MemError::Unmapped(0x028000)));
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use snes_emulator::{Rewind, System};
+
+fn create_counter_rom() -> Vec<u8> {
+    // Conta instruções em $10 sem parar
+    common::build_rom(&[
+        0xE6, 0x10,       // INC $10
+        0x4C, 0x00, 0x80, // JMP $8000
+    ], "REWIND TEST")
+}
+
+#[test]
+fn test_rewind_restores_latest_snapshot() {
+    let mut system = System::new(create_counter_rom());
+    let mut rewind = Rewind::new(3, 2);
+
+    let mut snapshots = Vec::new();
+    for _ in 0..8 {
+        system.run_frame();
+        rewind.capture(&system);
+        snapshots.push(system.save_state());
+    }
+
+    // 8 frames com intervalo 2 geram 4 snapshots, limitados a 3
+    assert_eq!(rewind.len(), 3);
+
+    system.memory.wram[0x10] = 0xEE;
+    system.cpu.pc = 0x009000;
+    system.run_frame();
+
+    assert!(rewind.rewind_one_frame(&mut system));
+    assert_eq!(system.save_state(), snapshots[7]);
+
+    assert!(rewind.rewind_one_frame(&mut system));
+    assert_eq!(system.save_state(), snapshots[5]);
+
+    assert!(rewind.rewind_one_frame(&mut system));
+    assert_eq!(system.save_state(), snapshots[3]);
+
+    // O snapshot do frame 2 foi descartado pela capacidade
+    assert!(rewind.is_empty());
+    assert!(!rewind.rewind_one_frame(&mut system));
+    assert_eq!(system.save_state(), snapshots[3]);
+}
+
+#[test]
+fn test_rewind_restores_ppu_state() {
+    let mut system = System::new(create_counter_rom());
+    let mut rewind = Rewind::new(1, 1);
+
+    system.run_frame();
+    system.memory.write(0x002121, 0x00);
+    system.memory.write(0x002122, 0x1F);
+    system.memory.write(0x002122, 0x00);
+    system.memory.write_oam(0x10, 0x44);
+    system.get_ppu_mut().write_register(0x2105, 0x01);
+    rewind.capture(&system);
+
+    let scanline = system.get_ppu().scanline;
+    let dot = system.get_ppu().cycle;
+
+    // Meio frame depois, com paleta, sprites e registradores diferentes
+    system.run_cycles(341 * 50 / 4);
+    system.memory.write(0x002121, 0x00);
+    system.memory.write(0x002122, 0xE0);
+    system.memory.write(0x002122, 0x03);
+    system.memory.write_oam(0x10, 0x00);
+    system.get_ppu_mut().write_register(0x2105, 0x07);
+    assert_ne!(system.get_ppu().scanline, scanline);
+
+    assert!(rewind.rewind_one_frame(&mut system));
+
+    let ppu = system.get_ppu();
+    assert_eq!(ppu.scanline, scanline);
+    assert_eq!(ppu.cycle, dot);
+    assert_eq!(ppu.cgram_addr, 0x02);
+    assert_eq!(ppu.shadow_registers().bgmode, 0x01);
+    drop(ppu);
+
+    assert_eq!(system.memory.read_cgram(0), 0x1F);
+    assert_eq!(system.memory.read_cgram(1), 0x00);
+    assert_eq!(system.memory.read_oam(0x10), 0x44);
+}
@@ -1,4 +1,4 @@
-use snes_emulator::System;
+use snes_emulator::{RunStop, System};
 use snes_emulator::opcodes;
 use std::fs;
 use std::path::Path;
@@ -49,11 +49,14 @@ fn execute_rom_test(rom_name: &str, max_instructions: usize, save_frames: bool)
     };
     
     let mut system = System::new(rom_data);
-    
+    run_system(rom_name, &mut system, max_instructions, save_frames)
+}
+
+fn run_system(rom_name: &str, system: &mut System, max_instructions: usize, save_frames: bool) -> RomTestResult {
     // Configura reset vector
     let reset_low = system.memory.read(0x00FFFC) as u32;
     let reset_high = system.memory.read(0x00FFFD) as u32;
-    system.cpu.pc = (reset_high << 8) | reset_low;
+    system.cpu.set_full_pc((reset_high << 8) | reset_low);
     
     println!("ROM Title: {}", system.memory.get_rom_title());
     println!("ROM Type: {:?}", system.memory.rom_type);
@@ -61,92 +64,82 @@ fn execute_rom_test(rom_name: &str, max_instructions: usize, save_frames: bool)
     println!("Estado inicial PPU: Scanline {}, Cycle {}", system.get_scanline(), system.get_ppu().cycle);
     
     let mut instructions_executed = 0;
-    let mut last_pc = system.cpu.pc;
-    let mut loop_counter = 0;
     let mut frame_count = 0;
-    
-    for i in 0..max_instructions {
-        let current_pc = system.cpu.pc;
-        let opcode = system.memory.read(current_pc);
-        
-        // Log primeiras instruções
-        if i < 20 {
-            println!("${:04X}: {:02X} - {} | PPU: L{} C{}", 
-                     current_pc, opcode, system.get_cpu_state(),
-                     system.get_scanline(), system.get_ppu().cycle);
-        }
-        
-        // Verifica se o opcode é válido antes de executar
-        if opcodes::get_opcode_info(opcode).is_none() {
-            let error_msg = format!("Unknown opcode: {:02X} at PC: {:06X}", opcode, current_pc);
-            println!("{}", error_msg);
-            
-            return RomTestResult {
-                rom_name: rom_name.to_string(),
-                success: false,
-                error_message: Some(error_msg),
-                instructions_executed,
-                final_state: system.get_cpu_state(),
-                frames_generated: frame_count,
-            };
-        }
-        
-        // Executa instrução (CPU + PPU integrados)
-        let cycles = system.step();
-        instructions_executed += 1;
-        
-        // Verifica se frame está pronto
-        if system.frame_ready() {
-            frame_count += 1;
-            println!("Frame {} pronto! Scanline: {}, Cycle: {}, Instrução: {}", 
-                    frame_count, system.get_scanline(), system.get_ppu().cycle, i);
-            
-            // Salva frame como imagem (se solicitado)
-            if save_frames && frame_count <= 10 {
-                save_frame_as_ppm(
-                    &format!("frame_{}_{:03}.ppm", rom_name.replace(".smc", ""), frame_count), 
-                    system.get_framebuffer().as_slice()
-                );
-                println!("Frame {} salvo como frame_{}_{:03}.ppm", frame_count, rom_name.replace(".smc", ""), frame_count);
+    let mut error_message = None;
+    let mut opcode = system.memory.read(system.cpu.full_pc());
+
+    let stop = if max_instructions == 0 || opcodes::get_opcode_info(opcode).is_none() {
+        RunStop::Condition
+    } else {
+        system.run_until(|system| {
+            let i = instructions_executed;
+            let executed = opcode;
+            instructions_executed += 1;
+
+            // Log primeiras instruções
+            if i < 20 {
+                println!("{:02X} -> {} | PPU: L{} C{}",
+                         executed, system.get_cpu_state(),
+                         system.get_scanline(), system.get_ppu().cycle);
             }
-        }
-        
-        // Detecta VBlank
-        if system.is_vblank() && i < 100 {
-            println!("  └─ VBlank ativo na instrução {}", i + 1);
-        }
-        
-        // Detecta loops infinitos
-        if system.cpu.pc == last_pc {
-            loop_counter += 1;
-            if loop_counter > 10 {
-                println!("Loop infinito detectado em ${:04X}", system.cpu.pc);
-                break;
+
+            // Verifica se frame está pronto
+            if system.frame_ready() {
+                frame_count += 1;
+                println!("Frame {} pronto! Scanline: {}, Cycle: {}, Instrução: {}",
+                        frame_count, system.get_scanline(), system.get_ppu().cycle, i);
+
+                // Salva frame como imagem (se solicitado)
+                if save_frames && frame_count <= 10 {
+                    save_frame_as_ppm(
+                        &format!("frame_{}_{:03}.ppm", rom_name.replace(".smc", ""), frame_count),
+                        system.get_framebuffer().as_slice()
+                    );
+                    println!("Frame {} salvo como frame_{}_{:03}.ppm", frame_count, rom_name.replace(".smc", ""), frame_count);
+                }
             }
-        } else {
-            loop_counter = 0;
-            last_pc = system.cpu.pc;
-        }
-        
-        // Para em endereços especiais ou BRK
-        if opcode == 0x00 {
-            println!("BRK instruction executed");
-            break;
-        }
-        
-        if system.cpu.pc == 0xFFFF || system.cpu.pc == 0x0000 {
-            println!("PC em endereço especial: ${:04X}", system.cpu.pc);
-            break;
-        }
-        
-        // Log esporádico
-        if i > 20 && i % 500 == 0 {
-            println!("Instrução {}: ${:04X}: {:02X} - {} | PPU: L{} C{}", 
-                     i, current_pc, opcode, system.get_cpu_state(),
-                     system.get_scanline(), system.get_ppu().cycle);
-        }
+
+            // Detecta VBlank
+            if system.is_vblank() && i < 100 {
+                println!("  └─ VBlank ativo na instrução {}", i + 1);
+            }
+
+            // Para em endereços especiais ou BRK
+            if executed == 0x00 {
+                println!("BRK instruction executed");
+                return true;
+            }
+
+            if system.cpu.pc == 0xFFFF || system.cpu.pc == 0x0000 {
+                println!("PC em endereço especial: ${:04X}", system.cpu.pc);
+                return true;
+            }
+
+            // Log esporádico
+            if i > 20 && i % 500 == 0 {
+                println!("Instrução {}: {:02X} - {} | PPU: L{} C{}",
+                         i, executed, system.get_cpu_state(),
+                         system.get_scanline(), system.get_ppu().cycle);
+            }
+
+            // Verifica se o próximo opcode é válido antes de executar
+            opcode = system.memory.read(system.cpu.full_pc());
+            if opcodes::get_opcode_info(opcode).is_none() {
+                return true;
+            }
+
+            instructions_executed >= max_instructions
+        })
+    };
+
+    if let RunStop::InfiniteLoop { pc, .. } = stop {
+        println!("Loop infinito detectado em ${:04X}", pc);
+    } else if opcodes::get_opcode_info(opcode).is_none() {
+        let error_msg = format!("Unknown opcode: {:02X} at PC: {:06X}", opcode, system.cpu.pc);
+        println!("{}", error_msg);
+        error_message = Some(error_msg);
     }
-    
+
     let success = instructions_executed > 10;
     
     println!("\n=== RESUMO ===");
@@ -162,8 +155,8 @@ fn execute_rom_test(rom_name: &str, max_instructions: usize, save_frames: bool)
     
     RomTestResult {
         rom_name: rom_name.to_string(),
-        success,
-        error_message: None,
+        success: success && error_message.is_none(),
+        error_message,
         instructions_executed,
         final_state: system.get_cpu_state(),
         frames_generated: frame_count,
@@ -218,6 +211,7 @@ fn test_sbc_rom() {
 }
 
 #[test]
+#[ignore = "snes_test_tsc.smc não está incluída em test_roms/"]
 fn test_tsc_rom() {
     let result = execute_rom_test("snes_test_tsc.smc", 1000, false);
     println!("\nResultado snes_test_tsc.smc:");
@@ -269,7 +263,7 @@ fn test_all_available_roms() {
     }
     
     println!("\n=== RESUMO DOS RESULTADOS ===");
-    println!("{:<30} | {:<10} | {:<15} | {:<8} | {}", "ROM", "Status", "Instruções", "Frames", "Erro");
+    println!("{:<30} | {:<10} | {:<15} | {:<8} | Erro", "ROM", "Status", "Instruções", "Frames");
     println!("{}", "-".repeat(90));
     
     for result in &results {
@@ -367,9 +361,9 @@ fn test_rom_diagnosis() {
     println!("\n=== DUMP DA MEMÓRIA NO RESET VECTOR ===");
     if emu_reset != 0x0000 && emu_reset < 0xFFFF {
         println!("Primeiros 16 bytes em ${:04X}:", emu_reset);
-        for i in 0..16 {
-            let addr = emu_reset.wrapping_add(i);
-            let byte = system.memory.read(addr as u32);
+        let bytes = system.memory.dump_region(emu_reset as u32, 16);
+        for (i, byte) in bytes.iter().enumerate() {
+            let addr = emu_reset.wrapping_add(i as u16);
             print!("${:04X}: {:02X} ", addr, byte);
             if (i + 1) % 8 == 0 { println!(); }
         }
@@ -421,3 +415,106 @@ fn test_super_mario_world_quick() {
     
     assert!(result.instructions_executed > 0, "Nenhuma instrução foi executada");
 }
+
+// ROM homebrew mínima: inicializa a PPU, envia tiles e tilemap por DMA,
+// liga o NMI e espera no loop principal até o handler de NMI rodar
+fn create_homebrew_rom() -> Vec<u8> {
+    let mut program: Vec<u8> = vec![
+        0x78,             // SEI
+        0x18,             // CLC
+        0xFB,             // XCE (modo nativo)
+        0xA9, 0x80,       // LDA #$80
+        0x8D, 0x00, 0x21, // STA $2100 (forced blank)
+
+        // DMA 0: paleta inteira branca ($FF fixo de $00:A000 para $2122)
+        0xA9, 0x00, 0x8D, 0x21, 0x21, // CGADD = 0
+        0xA9, 0x08, 0x8D, 0x00, 0x43, // DMAP: A-bus fixo, modo 0
+        0xA9, 0x22, 0x8D, 0x01, 0x43, // BBAD = $2122
+        0xA9, 0x00, 0x8D, 0x02, 0x43, // A1T low
+        0xA9, 0xA0, 0x8D, 0x03, 0x43, // A1T high
+        0xA9, 0x00, 0x8D, 0x04, 0x43, // A1B
+        0xA9, 0x00, 0x8D, 0x05, 0x43, // DAS low
+        0xA9, 0x02, 0x8D, 0x06, 0x43, // DAS high (512 bytes)
+        0xA9, 0x01, 0x8D, 0x0B, 0x42, // MDMAEN canal 0
+
+        // DMA 0: 32 bytes de $00:A000 (fonte fixa) para a word $1000 da VRAM
+        0xA9, 0x80, 0x8D, 0x15, 0x21, // VMAIN: incrementa após $2119
+        0xA9, 0x00, 0x8D, 0x16, 0x21, // VMADDL
+        0xA9, 0x10, 0x8D, 0x17, 0x21, // VMADDH
+        0xA9, 0x09, 0x8D, 0x00, 0x43, // DMAP: A-bus fixo, modo 1 ($2118/$2119)
+        0xA9, 0x18, 0x8D, 0x01, 0x43, // BBAD = $2118
+        0xA9, 0x00, 0x8D, 0x02, 0x43, // A1T low
+        0xA9, 0xA0, 0x8D, 0x03, 0x43, // A1T high
+        0xA9, 0x20, 0x8D, 0x05, 0x43, // DAS low
+        0xA9, 0x00, 0x8D, 0x06, 0x43, // DAS high
+        0xA9, 0x01, 0x8D, 0x0B, 0x42, // MDMAEN canal 0
+
+        // DMA 0: tilemap de $00:9000 para VRAM $0000 (0x800 bytes)
+        0xA9, 0x01, 0x8D, 0x00, 0x43, // DMAP: incremento, modo 1
+        0xA9, 0x00,
+        0x8D, 0x16, 0x21, // VMADDL
+        0x8D, 0x17, 0x21, // VMADDH
+        0x8D, 0x02, 0x43, // A1T low
+        0x8D, 0x05, 0x43, // DAS low
+        0xA9, 0x90, 0x8D, 0x03, 0x43, // A1T high
+        0xA9, 0x08, 0x8D, 0x06, 0x43, // DAS high
+        0xA9, 0x01, 0x8D, 0x0B, 0x42, // MDMAEN canal 0
+
+        0xA9, 0x00, 0x8D, 0x05, 0x21, // BGMODE = modo 0
+        0xA9, 0x01, 0x8D, 0x2C, 0x21, // TM = BG1
+        0xA9, 0x0F, 0x8D, 0x00, 0x21, // Tela ligada, brilho máximo
+        0xA9, 0x80, 0x8D, 0x00, 0x42, // NMITIMEN: NMI ligado
+
+        0xA5, 0x10,       // main: LDA $10
+        0xF0, 0xFC,       // BEQ main
+    ];
+
+    // Loop final depois que o NMI rodou
+    let done = 0x8000 + program.len() as u16;
+    program.extend_from_slice(&[0x4C, done as u8, (done >> 8) as u8]); // JMP done
+
+    let nmi_handler = 0x8000 + program.len() as u16;
+    program.extend_from_slice(&[
+        0xE6, 0x10, // INC $10
+        0x40,       // RTI
+    ]);
+
+    let mut rom = vec![0; 0x10000];
+    rom[..program.len()].copy_from_slice(&program);
+
+    // Tilemap em $9000: todas as entradas usam o tile $200 (word $1000)
+    for entry in rom[0x1000..0x1800].chunks_mut(2) {
+        entry[0] = 0x00;
+        entry[1] = 0x02;
+    }
+    rom[0x2000] = 0xFF; // Fonte fixa dos tiles em $A000
+
+    let header_start = 0x7FC0;
+    rom[header_start..header_start + 21].copy_from_slice(b"HOMEBREW TEST        ");
+
+    // Vetor de NMI nativo e vetor de reset
+    rom[0x7FEA] = nmi_handler as u8;
+    rom[0x7FEB] = (nmi_handler >> 8) as u8;
+    rom[0x7FFC] = 0x00;
+    rom[0x7FFD] = 0x80;
+
+    rom
+}
+
+#[test]
+fn test_homebrew_boot_reaches_main_loop() {
+    let mut system = System::new(create_homebrew_rom());
+    let result = run_system("homebrew", &mut system, 50000, false);
+
+    assert!(result.error_message.is_none(), "{:?}", result.error_message);
+    assert!(result.frames_generated > 0, "Nenhum frame gerado");
+
+    // O handler de NMI rodou e o programa chegou ao loop final
+    assert_eq!(system.memory.read(0x7E0010), 1);
+    assert!(system.get_ppu().nmi_enabled);
+    assert_eq!(system.memory.read_vram(0x2000), 0xFF);
+    assert_eq!(system.memory.read_vram(0x0001), 0x02);
+    assert_eq!(system.memory.read_cgram(0x1FF), 0xFF);
+
+    assert!(system.get_framebuffer().iter().any(|&pixel| pixel != 0), "Frame em branco");
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use snes_emulator::state::{diff_states, MemDiff, StateRegion};
+use snes_emulator::System;
+
+fn create_test_rom() -> Vec<u8> {
+    let mut rom = common::build_rom(&[], "STATE TEST");
+    rom[0x7FD8] = 0x01; // 2KB de SRAM
+    rom
+}
+
+#[test]
+fn test_diff_states_reports_changed_bytes() {
+    let mut system = System::new(create_test_rom());
+    let before = system.save_state();
+
+    system.memory.write(0x7E0123, 0x55);
+    let after = system.save_state();
+
+    assert_eq!(
+        diff_states(&before, &after),
+        vec![MemDiff { region: StateRegion::Wram, addr: 0x7E0123, before: 0x00, after: 0x55 }]
+    );
+
+    // SRAM e VRAM aparecem como offsets
+    system.memory.write(0x006010, 0xAA);
+    system.memory.write_vram(0x0200, 0x11);
+    let last = system.save_state();
+
+    assert_eq!(
+        diff_states(&after, &last),
+        vec![
+            MemDiff { region: StateRegion::Sram, addr: 0x0010, before: 0x00, after: 0xAA },
+            MemDiff { region: StateRegion::Vram, addr: 0x0200, before: 0x00, after: 0x11 },
+        ]
+    );
+
+    assert!(diff_states(&last, &last).is_empty());
+    assert!(diff_states(b"lixo", &last).is_empty());
+}
@@ -0,0 +1,537 @@
+mod common;
+
+use snes_emulator::memory::{DmaTransfer, Region};
+use snes_emulator::{Button, CpuTestState, JoypadState, Ppu, RenderError, RunStop, System, TimingMode};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Instant;
+
+fn create_test_rom(program: &[u8]) -> Vec<u8> {
+    common::build_rom(program, "SYSTEM TEST")
+}
+
+#[test]
+fn test_frame_profile() {
+    let rom = create_test_rom(&[
+        0xA9, 0x0F,       // LDA #$0F
+        0x8D, 0x00, 0x21, // STA $2100 (tela ligada, brilho máximo)
+        0x4C, 0x05, 0x80, // JMP $8005
+    ]);
+    let mut system = System::new(rom);
+
+    assert!(system.get_frame_profile().is_none());
+    system.set_profiling(true);
+
+    let start = Instant::now();
+    while system.get_frame_profile().is_none() {
+        system.step();
+    }
+    let elapsed = start.elapsed().as_nanos() as u64;
+
+    let profile = system.get_frame_profile().unwrap();
+    assert!(profile.cpu_nanos > 0);
+    assert!(profile.render_nanos > 0);
+    assert!(profile.cpu_nanos + profile.render_nanos <= elapsed);
+    assert!(profile.cpu_cycles > 0);
+}
+
+#[test]
+fn test_autosave_writes_srm_next_to_rom() {
+    assert_eq!(System::default_save_path("roms/game.smc"), "roms/game.srm");
+    assert_eq!(System::default_save_path("game"), "game.srm");
+
+    let mut rom = create_test_rom(&[]);
+    rom[0x7FD8] = 0x01; // 2KB de SRAM
+    let mut system = System::new(rom);
+
+    let dir = std::env::temp_dir().join(format!("snes_autosave_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let rom_path = dir.join("game.smc");
+    let srm_path = dir.join("game.srm");
+    system.set_rom_path(rom_path.to_str().unwrap());
+
+    // Nada mudou: não grava
+    assert!(!system.autosave_if_dirty().unwrap());
+    assert!(!srm_path.exists());
+
+    system.memory.write(0x006000, 0x5A);
+    system.memory.write(0x0067FF, 0xA5);
+    assert!(system.autosave_if_dirty().unwrap());
+
+    let saved = std::fs::read(&srm_path).unwrap();
+    assert_eq!(saved.len(), 0x800);
+    assert_eq!(saved[0], 0x5A);
+    assert_eq!(saved[0x7FF], 0xA5);
+
+    // Flag limpa depois do save
+    assert!(!system.autosave_if_dirty().unwrap());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_run_until_detects_infinite_loop() {
+    let rom = create_test_rom(&[
+        0xA9, 0x01,       // LDA #$01
+        0xEA,             // NOP
+        0x4C, 0x03, 0x80, // JMP $8003
+    ]);
+    let mut system = System::new(rom);
+
+    let stop = system.run_until(|_| false);
+    assert_eq!(stop, RunStop::InfiniteLoop { pc: 0x8003, count: 10 });
+
+    // Condição satisfeita antes do limite
+    let mut system = System::new(create_test_rom(&[0xEA, 0xEA, 0x4C, 0x02, 0x80]));
+    let stop = system.run_until(|system| system.cpu.pc == 0x8002);
+    assert_eq!(stop, RunStop::Condition);
+
+    system.set_loop_threshold(3);
+    assert_eq!(system.run_until(|_| false), RunStop::InfiniteLoop { pc: 0x8002, count: 3 });
+}
+
+#[test]
+fn test_cycle_budget_stops_runaway_frames() {
+    let rom = create_test_rom(&[
+        0xA9, 0x80,       // LDA #$80
+        0x8D, 0x00, 0x21, // STA $2100 (forced blank)
+        0x4C, 0x05, 0x80, // JMP $8005
+    ]);
+    let mut system = System::new(rom);
+
+    // Orçamento menor que um frame: run_frame desiste em vez de travar
+    system.set_cycle_budget(1000);
+    match system.run_frame() {
+        RunStop::Timeout { cycles } => assert!((1000..1010).contains(&cycles)),
+        stop => panic!("esperava Timeout, veio {:?}", stop),
+    }
+    assert!(!system.is_vblank());
+
+    system.set_loop_threshold(0);
+    assert!(matches!(system.run_until(|_| false), RunStop::Timeout { .. }));
+
+    // Com o orçamento padrão o loop de espera não impede o frame de fechar
+    system.set_cycle_budget(System::DEFAULT_CYCLE_BUDGET);
+    assert_eq!(system.run_frame(), RunStop::Condition);
+    assert!(system.is_vblank());
+}
+
+#[test]
+fn test_region_and_video_mode_queries() {
+    let mut rom = create_test_rom(&[]);
+    rom[0x7FD9] = 0x02; // Código de país: Europa
+    let system = System::new(rom);
+
+    assert_eq!(system.region(), Region::Pal);
+    assert_eq!(system.get_ppu().active_resolution(), (256, 224));
+    assert_eq!(system.get_ppu().aspect_ratio(), (8, 7));
+
+    // SETINI: pseudo-hires + overscan + entrelaçado
+    system.get_ppu_mut().write_register(0x2133, 0x0D);
+    assert_eq!(system.get_ppu().active_resolution(), (512, 478));
+    assert_eq!(system.get_ppu().aspect_ratio(), (8, 7));
+
+    system.get_ppu_mut().write_register(0x2133, 0x08);
+    assert_eq!(system.get_ppu().active_resolution(), (512, 224));
+    assert_eq!(system.get_ppu().aspect_ratio(), (4, 7));
+
+    rom = create_test_rom(&[]);
+    rom[0x7FD9] = 0x01; // Estados Unidos
+    let mut system = System::new(rom);
+    assert_eq!(system.region(), Region::Ntsc);
+
+    system.set_region(Region::Pal);
+    assert_eq!(system.region(), Region::Pal);
+}
+
+#[test]
+fn test_with_framebuffer_borrows_pixels() {
+    let system = System::new(create_test_rom(&[]));
+    system.get_ppu_mut().framebuffer[300] = 0x00FF00;
+
+    let pixel = system.with_framebuffer(|pixels| {
+        assert_eq!(pixels.len(), 256 * 224);
+        pixels[300]
+    });
+
+    assert_eq!(pixel, 0x00FF00);
+    assert_eq!(system.get_framebuffer()[300], pixel);
+}
+
+#[test]
+fn test_region_configures_scanlines_per_frame() {
+    let mut rom = create_test_rom(&[]);
+    rom[0x7FD9] = 0x09; // Alemanha
+    let mut system = System::new(rom);
+
+    assert_eq!(system.memory.detect_region(), Region::Pal);
+    assert_eq!(system.get_ppu().scanlines_per_frame, 312);
+
+    // Linhas 262-311 só existem no PAL
+    while system.get_scanline() < 300 {
+        system.step();
+    }
+
+    system.set_region(Region::Ntsc);
+    assert_eq!(system.get_ppu().scanlines_per_frame, 262);
+    assert_eq!(system.memory.detect_region(), Region::Pal);
+
+    // A linha atual já passou do fim do frame NTSC: a próxima volta para 0
+    let line = system.get_scanline();
+    while system.get_scanline() == line {
+        system.step();
+    }
+    assert_eq!(system.get_scanline(), 0);
+
+    system.reset();
+    assert_eq!(system.get_ppu().scanlines_per_frame, 262);
+}
+
+#[test]
+fn test_dma_and_refresh_stall_cpu() {
+    let rom = create_test_rom(&[
+        0xA9, 0x01,       // LDA #$01
+        0x8D, 0x0B, 0x42, // STA $420B (DMA canal 0)
+    ]);
+    let mut system = System::new(rom);
+
+    // Canal 0: 256 bytes de $7E:1000 para $2118
+    system.memory.write(0x004300, 0x00);
+    system.memory.write(0x004301, 0x18);
+    system.memory.write(0x004302, 0x00);
+    system.memory.write(0x004303, 0x10);
+    system.memory.write(0x004304, 0x7E);
+    system.memory.write(0x004305, 0x00);
+    system.memory.write(0x004306, 0x01);
+
+    system.step(); // LDA
+
+    let dot = |system: &System| {
+        let ppu = system.get_ppu();
+        ppu.scanline as u64 * 341 + ppu.cycle as u64
+    };
+    let start_cycles = system.cpu.cycles;
+    let start_dot = dot(&system);
+    let start_line = system.get_scanline();
+
+    let cycles = system.step(); // STA $420B
+
+    // STA abs (4) + overhead (2) + 1 canal + 256 bytes, mais o refresh de cada scanline
+    let lines = (system.get_scanline() - start_line) as u32;
+    assert!(lines >= 3);
+    let expected = 4 + 2 + 1 + 256 + lines * System::REFRESH_STALL_CYCLES;
+
+    assert_eq!(cycles, expected);
+    assert_eq!(system.cpu.cycles - start_cycles, expected as u64);
+    assert_eq!(dot(&system) - start_dot, expected as u64 * 4);
+}
+
+#[test]
+fn test_per_cycle_timing_mode() {
+    let program = [
+        0xAD, 0x00, 0x10, // LDA $1000 (4 ciclos)
+        0xEA,             // NOP (2 ciclos)
+    ];
+    let mut end = System::new(create_test_rom(&program));
+    let mut per_cycle = System::new(create_test_rom(&program));
+    per_cycle.set_timing_mode(TimingMode::PerCycle);
+    assert_eq!(end.timing_mode(), TimingMode::InstructionEnd);
+
+    let dot = |system: &System| system.get_ppu().cycle;
+    let start = dot(&end);
+    assert_eq!(dot(&per_cycle), start);
+
+    end.step();
+    per_cycle.step();
+
+    // 4 dots por ciclo contra 8 master cycles (2 dots) por ciclo na SlowROM
+    assert_eq!(dot(&end), start + 16);
+    assert_eq!(dot(&per_cycle), start + 8);
+
+    // O NOP fecha 16 master cycles = 4 dots
+    end.step();
+    per_cycle.step();
+    assert_eq!(dot(&end), start + 24);
+    assert_eq!(dot(&per_cycle), start + 12);
+    assert_eq!(end.cpu.cycles, per_cycle.cpu.cycles);
+}
+
+// BG1 com uma linha vermelha por tile e NMI ligado; no 9º NMI roda `last_frame`
+fn create_frame_counter_rom(last_frame: &[u8]) -> Vec<u8> {
+    let mut program = vec![
+        0x9C, 0x21, 0x21, // STZ $2121
+        0x9C, 0x22, 0x21, // STZ $2122
+        0x9C, 0x22, 0x21, // STZ $2122
+        0xA9, 0x1F,       // LDA #$1F
+        0x8D, 0x22, 0x21, // STA $2122
+        0x9C, 0x22, 0x21, // STZ $2122 (cor 1 = vermelho)
+        0xA9, 0x80,       // LDA #$80
+        0x8D, 0x15, 0x21, // STA $2115
+        0x9C, 0x16, 0x21, // STZ $2116
+        0x9C, 0x17, 0x21, // STZ $2117
+        0xA9, 0xFF,       // LDA #$FF
+        0x8D, 0x18, 0x21, // STA $2118
+        0x9C, 0x19, 0x21, // STZ $2119 (tile 0, linha 0 toda na cor 1)
+        0xA9, 0x04,       // LDA #$04
+        0x8D, 0x07, 0x21, // STA $2107 (tilemap do BG1 em $0400)
+        0xA9, 0x01,       // LDA #$01
+        0x8D, 0x2C, 0x21, // STA $212C (BG1 na main screen)
+        0xA9, 0x0F,       // LDA #$0F
+        0x8D, 0x00, 0x21, // STA $2100
+        0xA9, 0x80,       // LDA #$80
+        0x8D, 0x00, 0x42, // STA $4200 (NMI ligado)
+        0x4C, 0x38, 0x80, // JMP $8038
+    ];
+    program.resize(0x40, 0xEA);
+
+    // Handler de NMI em $8040
+    program.extend_from_slice(&[
+        0xEE, 0x10, 0x00, // INC $0010
+        0xAD, 0x10, 0x00, // LDA $0010
+        0xC9, 0x09,       // CMP #$09
+        0xD0, last_frame.len() as u8,
+    ]);
+    program.extend_from_slice(last_frame);
+    program.push(0x40); // RTI
+
+    let mut rom = create_test_rom(&program);
+    rom[0x7FFA] = 0x40; // Vetor de NMI (emulação)
+    rom[0x7FFB] = 0x80;
+    rom
+}
+
+#[test]
+fn test_run_frames_fast_renders_only_last_frame() {
+    let forced_blank = [0xA9, 0x80, 0x8D, 0x00, 0x21]; // LDA #$80; STA $2100
+    let blue = [
+        0x9C, 0x21, 0x21, // STZ $2121
+        0x9C, 0x22, 0x21, // STZ $2122
+        0x9C, 0x22, 0x21, // STZ $2122
+        0x9C, 0x22, 0x21, // STZ $2122
+        0xA9, 0x7C,       // LDA #$7C
+        0x8D, 0x22, 0x21, // STA $2122 (cor 1 = azul)
+    ];
+
+    for last_frame in [&forced_blank[..], &blue[..]] {
+        let rom = create_frame_counter_rom(last_frame);
+
+        // Referência: cada frame renderizado
+        let mut normal = System::new(rom.clone());
+        for _ in 0..10 {
+            normal.run_frames_fast(1);
+        }
+
+        let mut fast = System::new(rom);
+        fast.run_frames_fast(10);
+
+        // Mesmo timing: parado no 10º VBlank, com o handler desse NMI ainda por rodar
+        assert_eq!(fast.cpu.cycles, normal.cpu.cycles);
+        assert_eq!(fast.get_scanline(), Ppu::VBLANK_START_LINE);
+        assert_eq!(fast.cpu.pc, 0x8040);
+        assert_eq!(fast.memory.read(0x000010), 9);
+        assert_eq!(normal.memory.read(0x000010), 9);
+        assert!(!fast.get_ppu().skip_render);
+
+        let normal_fb = normal.get_framebuffer();
+        let fast_fb = fast.get_framebuffer();
+
+        if last_frame == &forced_blank[..] {
+            // O último frame ficou em forced blank: só o modo normal guarda os 9 anteriores
+            assert_ne!(normal_fb[0], 0);
+            assert!(fast_fb.iter().all(|&pixel| pixel == 0));
+        } else {
+            // O último frame (azul) é desenhado nos dois modos
+            assert_eq!(fast_fb, normal_fb);
+            assert_ne!(fast_fb[0], 0);
+        }
+    }
+}
+
+#[test]
+fn test_trace_to_writes_one_line_per_instruction() {
+    let program = [
+        0xA9, 0x80, // LDA #$80
+        0x38,       // SEC
+        0xA2, 0x05, // LDX #$05
+    ];
+    let mut system = System::new(create_test_rom(&program));
+
+    let mut buffer = Vec::new();
+    system.trace_to(&mut buffer, 3).unwrap();
+
+    let trace = String::from_utf8(buffer).unwrap();
+    let lines: Vec<&str> = trace.lines().collect();
+    assert_eq!(lines.len(), 3);
+
+    // Estado antes de cada instrução
+    assert_eq!(lines[0], "008000 A:0000 X:0000 Y:0000 SP:01FF P:nvMXdIzc");
+    assert_eq!(lines[1], "008002 A:0080 X:0000 Y:0000 SP:01FF P:NvMXdIzc");
+    assert_eq!(lines[2], "008003 A:0080 X:0000 Y:0000 SP:01FF P:NvMXdIzC");
+    assert_eq!(system.cpu.state_line(), "008005 A:0080 X:0005 Y:0000 SP:01FF P:nvMXdIzC");
+}
+
+#[test]
+fn test_run_single_test_on_flat_memory() {
+    // STA $2000 em modo nativo com A de 8 bits: grava $34 em $12:2000
+    let initial = CpuTestState {
+        pc: 0x1000,
+        sp: 0x01F0,
+        a: 0x1234,
+        x: 0x0001,
+        y: 0x0002,
+        db: 0x12,
+        dp: 0x0000,
+        pb: 0x00,
+        p: 0x30,
+        e: false,
+        ram: vec![
+            (0x001000, 0x8D),
+            (0x001001, 0x00),
+            (0x001002, 0x20),
+            (0x122000, 0x00),
+            (0x122001, 0x00),
+        ],
+    };
+
+    let expected = CpuTestState {
+        pc: 0x1003,
+        ram: vec![
+            (0x001000, 0x8D),
+            (0x001001, 0x00),
+            (0x001002, 0x20),
+            (0x122000, 0x34),
+            (0x122001, 0x00),
+        ],
+        ..initial.clone()
+    };
+
+    let mut system = System::flat();
+    let result = system.run_single_test(&initial, 4);
+    assert_eq!(result, expected);
+    assert_eq!(system.cpu.cycles, 4);
+
+    // Sem cartucho nem registradores: $2100 e $4200 são só RAM
+    system.memory.write(0x002100, 0x77);
+    assert_eq!(system.memory.read(0x002100), 0x77);
+    assert_eq!(system.memory.peek(0x002100), 0x77);
+}
+
+#[test]
+fn test_dma_log_records_transfers() {
+    let rom = create_test_rom(&[
+        0xA9, 0x04,       // LDA #$04
+        0x8D, 0x0B, 0x42, // STA $420B (DMA canal 2)
+        0x8D, 0x0B, 0x42, // STA $420B
+    ]);
+    let mut system = System::new(rom);
+    assert!(system.dma_log().is_empty());
+
+    // Canal 2: 512 bytes de $7E:2000 para VMDATA ($2118/$2119, modo 1)
+    system.memory.write(0x004320, 0x01);
+    system.memory.write(0x004321, 0x18);
+    system.memory.write(0x004322, 0x00);
+    system.memory.write(0x004323, 0x20);
+    system.memory.write(0x004324, 0x7E);
+    system.memory.write(0x004325, 0x00);
+    system.memory.write(0x004326, 0x02);
+
+    system.step(); // LDA
+    system.step(); // STA sem log
+    assert!(system.dma_log().is_empty());
+
+    system.memory.write(0x004322, 0x00);
+    system.memory.write(0x004323, 0x20);
+    system.memory.write(0x004326, 0x02);
+    system.enable_dma_log(true);
+    let scanline = system.get_scanline();
+    system.step();
+
+    assert_eq!(
+        system.dma_log(),
+        &[DmaTransfer { channel: 2, source: 0x7E2000, destination: 0x2118, length: 0x200, scanline }]
+    );
+
+    system.enable_dma_log(false);
+    assert!(system.dma_log().is_empty());
+}
+
+#[test]
+fn test_run_frame_into_reuses_caller_buffer() {
+    let mut system = System::new(create_frame_counter_rom(&[]));
+    let mut buffer = vec![0xDEADBEEF; 256 * 224];
+
+    for _ in 0..2 {
+        assert_eq!(system.run_frame_into(&mut buffer), Ok(RunStop::Condition));
+    }
+    assert_eq!(buffer, system.get_framebuffer());
+    assert!(buffer.iter().any(|&pixel| pixel != 0));
+    assert!(buffer.iter().all(|&pixel| pixel != 0xDEADBEEF));
+
+    // Buffer errado é recusado antes de rodar qualquer coisa
+    let cycles = system.cpu.cycles;
+    let mut small = vec![0; 256];
+    assert_eq!(
+        system.run_frame_into(&mut small),
+        Err(RenderError::BufferSize { expected: 256 * 224, actual: 256 })
+    );
+    assert_eq!(system.cpu.cycles, cycles);
+
+    // Frame que estoura o orçamento devolve o Timeout e não toca no buffer
+    system.set_cycle_budget(1000);
+    buffer.fill(0xDEADBEEF);
+    assert!(matches!(system.run_frame_into(&mut buffer), Ok(RunStop::Timeout { .. })));
+    assert!(buffer.iter().all(|&pixel| pixel == 0xDEADBEEF));
+}
+
+#[test]
+fn test_set_controller_fills_each_port() {
+    let mut system = System::new(create_test_rom(&[]));
+    system.set_controller(1, JoypadState::new().with(Button::A).with(Button::Start));
+    system.set_controller(2, JoypadState::new().with(Button::B));
+
+    // Os registradores só são preenchidos pelo auto-read no VBlank
+    assert_eq!(system.memory.read(0x004218), 0x00);
+    system.memory.write(0x004200, 0x01);
+    system.run_frame();
+
+    assert_eq!(system.memory.read(0x004218), 0x80);
+    assert_eq!(system.memory.read(0x004219), 0x10);
+    assert_eq!(system.memory.read(0x00421A), 0x00);
+    assert_eq!(system.memory.read(0x00421B), 0x80);
+
+    // Leitura manual: strobe e 16 leituras seriais por porta, do B ao R
+    system.memory.write(0x004016, 0x01);
+    system.memory.write(0x004016, 0x00);
+    let read_port = |system: &System, addr: u32| {
+        (0..16).fold(0u16, |acc, _| (acc << 1) | (system.memory.read(addr) & 0x01) as u16)
+    };
+    assert_eq!(read_port(&system, 0x004016), 0x1080);
+    assert_eq!(read_port(&system, 0x004017), 0x8000);
+    assert_eq!(system.memory.read(0x004017) & 0x01, 1);
+
+    // Auto-read no VBlank volta a latchear o estado de cada porta
+    system.memory.registers.insert(0x421A, 0xFF);
+    system.run_frame();
+    assert_eq!(system.memory.read(0x00421A), 0x00);
+    assert_eq!(system.memory.read(0x00421B), 0x80);
+}
+
+#[test]
+fn test_frame_callback_fires_once_per_frame() {
+    let mut system = System::new(create_test_rom(&[]));
+    let frames = Rc::new(Cell::new(0));
+
+    let counter = Rc::clone(&frames);
+    system.set_frame_callback(Box::new(move |framebuffer| {
+        assert_eq!(framebuffer.len(), 256 * 224);
+        counter.set(counter.get() + 1);
+    }));
+
+    // ~22.700 ciclos por frame NTSC: 60.000 ciclos cobrem dois inícios de VBlank
+    system.run_cycles(60_000);
+    assert_eq!(frames.get(), 2);
+
+    system.clear_frame_callback();
+    system.run_cycles(30_000);
+    assert_eq!(frames.get(), 2);
+}